@@ -0,0 +1,35 @@
+// Stringのkeyでinsertとremoveの速さを測る。以下のように実行する
+// cargo bench --bench throughput
+// 標準のbenchハーネスはnightlyでしか使えないので、main関数で時間を測って表示する
+use std::hint::black_box;
+use std::time::Instant;
+
+use rb_tree::RBTree;
+
+const LEN: usize = 1_000_000;
+
+fn main() {
+    for width in [16, 100] {
+        // 奇数を掛けてばらばらの順にしたkeyを、右寄せで同じ長さにそろえる
+        let keys: Vec<String> = (0..LEN as u64)
+            .map(|key| format!("{:>width$}", key.wrapping_mul(2654435761) % 10_000_000_000, width = width))
+            .collect();
+
+        let mut tree: RBTree<String, usize> = RBTree::new();
+        let start = Instant::now();
+        for (value, key) in keys.iter().enumerate() {
+            tree.insert(key.clone(), value);
+        }
+        let elapsed = start.elapsed();
+        println!("insert: {} keys of {} bytes in {:?}, {:.0} inserts/s", LEN, width, elapsed, LEN as f64 / elapsed.as_secs_f64());
+        assert_eq!(LEN, tree.len());
+
+        let start = Instant::now();
+        for key in keys.iter() {
+            black_box(tree.remove(key));
+        }
+        let elapsed = start.elapsed();
+        println!("remove: {} keys of {} bytes in {:?}, {:.0} removes/s", LEN, width, elapsed, LEN as f64 / elapsed.as_secs_f64());
+        assert!(tree.is_empty());
+    }
+}
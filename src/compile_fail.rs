@@ -0,0 +1,74 @@
+// SendやSyncにしてはいけない木が、そうなっていないことを確かめるdoctest
+// cargo testのdoctestとしてだけコンパイルされる
+
+/// valueがSendでない木は他のスレッドに渡せない
+///
+/// ```compile_fail,E0277
+/// fn assert_send<T: Send>() {}
+/// assert_send::<rb_tree::RBTree<usize, std::rc::Rc<usize>>>();
+/// ```
+///
+/// keyがSendでない場合も同じ
+///
+/// ```compile_fail,E0277
+/// fn assert_send<T: Send>() {}
+/// assert_send::<rb_tree::RBTree<std::rc::Rc<usize>, usize>>();
+/// ```
+struct NotSend;
+
+/// valueがSyncでない木は複数のスレッドから同時に読めない
+///
+/// ```compile_fail,E0277
+/// fn assert_sync<T: Sync>() {}
+/// assert_sync::<rb_tree::RBTree<usize, std::cell::Cell<usize>>>();
+/// ```
+///
+/// updateを溜める木は読み取りの途中でもノードを書き換えるので、Syncにならない
+///
+/// ```compile_fail,E0277
+/// use rb_tree::{Augment, RBTree};
+///
+/// struct AddSum;
+///
+/// impl Augment<usize, usize> for AddSum {
+///     type Summary = usize;
+///     type Update = usize;
+///
+///     fn empty() -> usize { 0 }
+///
+///     fn combine(left: &usize, node: (&usize, &usize), right: &usize) -> usize {
+///         left + node.1 + right
+///     }
+/// }
+///
+/// fn assert_sync<T: Sync>() {}
+/// assert_sync::<RBTree<usize, usize, AddSum>>();
+/// ```
+///
+/// Syncでない木への参照を持つイテレータは他のスレッドに渡せない
+///
+/// ```compile_fail,E0277
+/// let tree: rb_tree::RBTree<usize, std::cell::Cell<usize>> = rb_tree::RBTree::new();
+/// let iter = tree.iter();
+/// std::thread::scope(|scope| {
+///     scope.spawn(move || iter.count());
+/// });
+/// ```
+struct NotSync;
+
+/// IntrusiveRBTreeは値のRBLinkをCellで書き換えるので、SendにもSyncにもならない
+///
+/// ```compile_fail,E0277
+/// use rb_tree::intrusive::{IntrusiveRBTree, RBLink};
+///
+/// struct Timer {
+///     deadline: u64,
+///     link: RBLink,
+/// }
+///
+/// rb_tree::intrusive_adapter!(ByDeadline = Timer { link } => u64, |timer| &timer.deadline);
+///
+/// fn assert_send<T: Send>() {}
+/// assert_send::<IntrusiveRBTree<'static, ByDeadline>>();
+/// ```
+struct IntrusiveNotSend;
@@ -0,0 +1,177 @@
+use core::borrow::Borrow;
+use core::fmt::Debug;
+use core::mem;
+use core::ops::Deref;
+
+use super::persistent::PersistentRBTree;
+use super::RBTree;
+
+// clone()ではノードを共有するだけでO(1)にして、変更する時に必要なノードだけをコピーする木
+// RBTreeとは別の型で、RBTreeの中身を共有するものではない
+// RBTreeのノードは親へのリンクを持つので複数の木から共有できないため、親へのリンクを持たないPersistentRBTreeのノードを共有する
+// そのためkeyはKのOrdの順に並び、AugmentやCompare、NodeHandleは使えない。それらが必要な場合はinto_innerでRBTreeに変換する
+// 変更では根から変更するノードまでのパスだけをコピーし、それ以外の部分木は共有したままにする
+// 読み取りはDerefでPersistentRBTreeのメソッドをそのまま使う
+pub struct CowRBTree<K: Ord + Clone + Debug, V: Clone> {
+    tree: PersistentRBTree<K, V>,
+}
+
+impl<K: Ord + Clone + Debug, V: Clone> CowRBTree<K, V> {
+    pub fn new() -> CowRBTree<K, V> {
+        CowRBTree {
+            tree: PersistentRBTree::new(),
+        }
+    }
+
+    // 根のノードを他のCowRBTreeと共有しているかを返す
+    // 共有していなくても、変更していない部分木は共有したままになっている
+    pub fn is_shared(&self) -> bool {
+        self.tree.is_shared()
+    }
+
+    // keyのvalueを書き換えられるようにする。他の木と共有しているノードはここでコピーする
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+        where K: Borrow<Q>, Q: Ord + ?Sized {
+        self.tree.get_mut(key)
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        // すでにkeyが存在する場合は、木の形を変えずにvalueだけを書き換える
+        if let Some(old_value) = self.tree.get_mut(&key) {
+            return Some(mem::replace(old_value, value));
+        }
+        self.tree = self.tree.insert(key, value);
+        None
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+        where K: Borrow<Q>, Q: Ord + ?Sized {
+        // 存在しないkeyの場合は何もコピーしない
+        let value = self.tree.get(key)?.clone();
+        self.tree = self.tree.remove(key);
+        Some(value)
+    }
+
+    // RBTreeに変換する。ノードの形が違うので、keyの順に並べて作り直す
+    pub fn into_inner(self) -> RBTree<K, V> {
+        RBTree::from_sorted_iter(self.tree.iter().map(|(key, value)| (key.clone(), value.clone())))
+    }
+}
+
+impl<K: Ord + Clone + Debug, V: Clone> Default for CowRBTree<K, V> {
+    fn default() -> CowRBTree<K, V> {
+        CowRBTree::new()
+    }
+}
+
+impl<K: Ord + Clone + Debug, V: Clone> Clone for CowRBTree<K, V> {
+    fn clone(&self) -> CowRBTree<K, V> {
+        CowRBTree {
+            tree: self.tree.clone(),
+        }
+    }
+}
+
+impl<K: Ord + Clone + Debug, V: Clone> Deref for CowRBTree<K, V> {
+    type Target = PersistentRBTree<K, V>;
+
+    fn deref(&self) -> &PersistentRBTree<K, V> {
+        &self.tree
+    }
+}
+
+// RBTreeから昇順に取り出して、O(n)で組み立てる
+impl<K: Ord + Clone + Debug, V: Clone> From<RBTree<K, V>> for CowRBTree<K, V> {
+    fn from(tree: RBTree<K, V>) -> CowRBTree<K, V> {
+        CowRBTree {
+            tree: PersistentRBTree::from_sorted_iter(tree),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    // 2つの木の両方から辿れるノードの数
+    fn shared_nodes<K: Ord + Clone + Debug, V: Clone>(a: &CowRBTree<K, V>, b: &CowRBTree<K, V>) -> usize {
+        let nodes = a.iter().map(|(key, _)| key as *const K).collect::<BTreeSet<_>>();
+        b.iter().filter(|(key, _)| nodes.contains(&(*key as *const K))).count()
+    }
+
+    #[test]
+    fn copy_on_write() {
+        let mut tree: RBTree<usize, usize> = RBTree::new();
+        for key in 0..100 {
+            tree.insert(key, key);
+        }
+        let mut original = CowRBTree::from(tree);
+        let mut forked = original.clone();
+        assert!(original.is_shared());
+        assert_eq!(100, shared_nodes(&original, &forked));
+
+        // 存在しないkeyの変更ではコピーしない
+        assert_eq!(None, forked.remove(&100));
+        assert_eq!(None, forked.get_mut(&100));
+        assert!(forked.is_shared());
+
+        // 変更では根からのパスだけをコピーし、残りのノードは共有したままにする
+        assert_eq!(Some(5), forked.insert(5, 500));
+        assert!(!original.is_shared());
+        assert!(!forked.is_shared());
+        // 100ノードの赤黒木の高さは2 * log2(101)以下なので、コピーするのは14ノード未満
+        let shared = shared_nodes(&original, &forked);
+        assert!((100 - 2 * 7..100).contains(&shared));
+        // 共有していないノードはコピーせずにそのまま書き換える
+        *forked.get_mut(&5).unwrap() += 1;
+        assert_eq!(shared, shared_nodes(&original, &forked));
+
+        assert_eq!(Some(6), forked.remove(&6));
+        assert_eq!(None, forked.insert(200, 200));
+        assert_eq!(Some(&5), original.get(&5));
+        assert_eq!(Some(&501), forked.get(&5));
+        assert!(!forked.contains_key(&6));
+        assert_eq!(100, original.len());
+        assert_eq!(100, forked.len());
+
+        original.insert(100, 100);
+        let original = original.into_inner();
+        assert_eq!(Some((&100, &100)), original.last_key_value());
+        assert_eq!(101, original.len());
+        assert_eq!(Some((&200, &200)), forked.iter().last());
+    }
+
+    // 同じ操作をしたRBTreeと同じ結果になり、cloneした木を変更しても元の木は変わらない
+    #[test]
+    fn rb_tree_parity() {
+        let mut cow: CowRBTree<usize, usize> = CowRBTree::new();
+        let mut tree: RBTree<usize, usize> = RBTree::new();
+        let mut forks = vec![];
+        let mut seed = 11usize;
+        let mut random = |max: usize| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 33) % max
+        };
+        for step in 0..3000 {
+            let key = random(200);
+            match random(5) {
+                0 => assert_eq!(tree.remove(&key), cow.remove(&key)),
+                1 => {
+                    let expected = tree.get_mut(&key).map(|value| { *value += 1; *value });
+                    assert_eq!(expected, cow.get_mut(&key).map(|value| { *value += 1; *value }));
+                },
+                2 if step % 20 == 0 => forks.push((cow.clone(), tree.clone())),
+                _ => assert_eq!(tree.insert(key, step), cow.insert(key, step)),
+            }
+            assert_eq!(tree.get(&key), cow.get(&key));
+            assert_eq!(tree.len(), cow.len());
+        }
+        assert!(tree.iter().eq(cow.iter()));
+        assert!(!forks.is_empty());
+        for (cow, tree) in forks {
+            assert_eq!(tree, cow.into_inner());
+        }
+        assert_eq!(tree, CowRBTree::from(tree.clone()).into_inner());
+    }
+}
@@ -0,0 +1,164 @@
+use core::mem;
+
+use super::{Compare, Natural, NodeId, RBTree};
+
+// BTreeMapのentryと同じく、keyを1回だけ探して、存在するかどうかで処理を分ける
+// valueを書き換えるので、集約しない木だけで使える
+pub enum Entry<'a, K, V, C = Natural> {
+    Vacant(VacantEntry<'a, K, V, C>),
+    Occupied(OccupiedEntry<'a, K, V, C>),
+}
+
+pub struct VacantEntry<'a, K, V, C = Natural> {
+    tree: &'a mut RBTree<K, V, (), C>,
+    key: K,
+}
+
+pub struct OccupiedEntry<'a, K, V, C = Natural> {
+    tree: &'a mut RBTree<K, V, (), C>,
+    node: NodeId,
+}
+
+impl<K, V, C: Compare<K>> RBTree<K, V, (), C> {
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, C> {
+        match self.search(&key) {
+            Some(node) => Entry::Occupied(OccupiedEntry { tree: self, node }),
+            None => Entry::Vacant(VacantEntry { tree: self, key }),
+        }
+    }
+}
+
+impl<'a, K, V, C: Compare<K>> Entry<'a, K, V, C> {
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Vacant(entry) => entry.key(),
+            Entry::Occupied(entry) => entry.key(),
+        }
+    }
+
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        self.or_insert_with_key(|_| default())
+    }
+
+    pub fn or_insert_with_key<F: FnOnce(&K) -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Vacant(entry) => {
+                let value = default(entry.key());
+                entry.insert(value)
+            },
+            Entry::Occupied(entry) => entry.into_mut(),
+        }
+    }
+
+    pub fn or_default(self) -> &'a mut V
+        where V: Default {
+        self.or_insert_with(V::default)
+    }
+
+    // 存在する場合だけvalueを書き換える
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, f: F) -> Entry<'a, K, V, C> {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+}
+
+impl<'a, K, V, C: Compare<K>> VacantEntry<'a, K, V, C> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    // 挿入したノードはその後の回転でも位置(NodeId)が変わらないので、そのままvalueを返せる
+    pub fn insert(self, value: V) -> &'a mut V {
+        let (node, _) = self.tree.insert_node(self.key, value);
+        self.tree.node_mut(node).value_mut()
+    }
+}
+
+impl<'a, K, V, C: Compare<K>> OccupiedEntry<'a, K, V, C> {
+    pub fn key(&self) -> &K {
+        &self.tree.node(self.node).key
+    }
+
+    pub fn get(&self) -> &V {
+        self.tree.node(self.node).value()
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        self.tree.node_mut(self.node).value_mut()
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        self.tree.node_mut(self.node).value_mut()
+    }
+
+    // valueを置き換えて、古いvalueを返す
+    pub fn insert(&mut self, value: V) -> V {
+        mem::replace(self.get_mut(), value)
+    }
+
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+
+    pub fn remove_entry(self) -> (K, V) {
+        self.tree.remove_node(self.node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entry() {
+        let mut tree: RBTree<&str, usize> = RBTree::new();
+        for word in "a b c a b a".split(' ') {
+            *tree.entry(word).or_insert(0) += 1;
+        }
+        assert_eq!(vec![(&"a", &3), (&"b", &2), (&"c", &1)], tree.iter().collect::<Vec<_>>());
+
+        tree.entry("c").and_modify(|count| *count *= 10).or_default();
+        tree.entry("d").and_modify(|count| *count *= 10).or_default();
+        assert_eq!(Some(&10), tree.get(&"c"));
+        assert_eq!(Some(&0), tree.get(&"d"));
+        assert_eq!(&"e", tree.entry("e").key());
+        assert_eq!(1, *tree.entry("e").or_insert_with_key(|key| key.len()));
+
+        match tree.entry("b") {
+            Entry::Occupied(mut entry) => {
+                assert_eq!(&"b", entry.key());
+                assert_eq!(2, entry.insert(20));
+                assert_eq!(&20, entry.get());
+            },
+            Entry::Vacant(_) => unreachable!(),
+        }
+        match tree.entry("a") {
+            Entry::Occupied(entry) => assert_eq!(("a", 3), entry.remove_entry()),
+            Entry::Vacant(_) => unreachable!(),
+        }
+        match tree.entry("a") {
+            Entry::Occupied(_) => unreachable!(),
+            Entry::Vacant(entry) => assert_eq!("a", entry.into_key()),
+        }
+        assert_eq!(vec![(&"b", &20), (&"c", &10), (&"d", &0), (&"e", &1)], tree.iter().collect::<Vec<_>>());
+
+        // 挿入で木の形が変わっても、返した参照は挿入したノードのvalueを指す
+        let mut tree: RBTree<usize, usize> = RBTree::new();
+        for key in 0..100 {
+            let value = tree.entry(key * 7 % 100).or_insert(0);
+            *value = key;
+        }
+        assert_eq!(100, tree.len());
+        assert!(tree.iter().all(|(key, value)| value * 7 % 100 == *key));
+    }
+}
@@ -0,0 +1,444 @@
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::fmt;
+use core::fmt::{Debug, Display, Formatter};
+use core::mem;
+
+use super::{Color, Links, NodeId, ParentColor};
+
+struct StaticNode<K, V> {
+    key: K,
+    value: V,
+    parent_color: ParentColor,
+    left: Option<NodeId>,
+    right: Option<NodeId>,
+}
+
+enum StaticSlot<K, V> {
+    Occupied(StaticNode<K, V>),
+    // 空いている枠。次に空いている枠を指して、空いている枠のリストを作る
+    Vacant(Option<NodeId>),
+}
+
+// 容量いっぱいの木に新しいkeyを挿入しようとした場合のエラー
+// 挿入できなかったkeyとvalueを持っているので、呼び出し側で取り戻せる
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct CapacityError<K, V> {
+    key: K,
+    value: V,
+}
+
+impl<K, V> CapacityError<K, V> {
+    pub fn into_inner(self) -> (K, V) {
+        (self.key, self.value)
+    }
+}
+
+impl<K, V> Debug for CapacityError<K, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CapacityError").finish_non_exhaustive()
+    }
+}
+
+impl<K, V> Display for CapacityError<K, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "rb-tree is full")
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K, V> std::error::Error for CapacityError<K, V> {}
+
+// 最大N個のノードを配列に持ち、ヒープを一切使わないRBTree
+// ノードの置き方はRBTreeのslabと同じく、NodeIdで親子を指して、空いた枠は空いている枠のリストに繋ぐ
+// 配列の枠は前から順に使い、usedより後ろの枠はまだ一度も使っていない
+pub struct StaticRBTree<K: Ord + Clone + Debug, V, const N: usize> {
+    nodes: [StaticSlot<K, V>; N],
+    free: Option<NodeId>,
+    used: usize,
+    root: Option<NodeId>,
+    len: usize,
+}
+
+impl<K: Ord + Clone + Debug, V, const N: usize> StaticRBTree<K, V, N> {
+    // staticに置けるようにconst fnにする
+    pub const fn new() -> StaticRBTree<K, V, N> {
+        StaticRBTree {
+            nodes: [const { StaticSlot::Vacant(None) }; N],
+            free: None,
+            used: 0,
+            root: None,
+            len: 0,
+        }
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    #[inline]
+    fn node(&self, id: NodeId) -> &StaticNode<K, V> {
+        match &self.nodes[id.index()] {
+            StaticSlot::Occupied(node) => node,
+            StaticSlot::Vacant(_) => panic!("rb-tree node({}) is already removed.", id.index()),
+        }
+    }
+
+    #[inline]
+    fn node_mut(&mut self, id: NodeId) -> &mut StaticNode<K, V> {
+        match &mut self.nodes[id.index()] {
+            StaticSlot::Occupied(node) => node,
+            StaticSlot::Vacant(_) => panic!("rb-tree node({}) is already removed.", id.index()),
+        }
+    }
+
+    // 空いている枠にノードを置く。空いている枠がなければノードを返す
+    fn allocate(&mut self, node: StaticNode<K, V>) -> Result<NodeId, StaticNode<K, V>> {
+        match self.free {
+            Some(id) => {
+                match mem::replace(&mut self.nodes[id.index()], StaticSlot::Occupied(node)) {
+                    StaticSlot::Vacant(next) => self.free = next,
+                    StaticSlot::Occupied(_) => unreachable!("free list points to an occupied slot"),
+                }
+                Ok(id)
+            },
+            None if self.used < N => {
+                let id = NodeId::new(self.used);
+                self.nodes[self.used] = StaticSlot::Occupied(node);
+                self.used += 1;
+                Ok(id)
+            },
+            None => Err(node),
+        }
+    }
+
+    // ノードを枠から取り出して、枠を空いている枠のリストに繋ぐ
+    fn deallocate(&mut self, id: NodeId) -> StaticNode<K, V> {
+        match mem::replace(&mut self.nodes[id.index()], StaticSlot::Vacant(self.free)) {
+            StaticSlot::Occupied(node) => {
+                self.free = Some(id);
+                node
+            },
+            StaticSlot::Vacant(_) => panic!("rb-tree node({}) is already removed.", id.index()),
+        }
+    }
+
+    fn search<Q>(&self, key: &Q) -> Option<NodeId>
+        where K: Borrow<Q>, Q: Ord + ?Sized {
+        let mut node = self.root?;
+        loop {
+            match key.cmp(self.node(node).key.borrow()) {
+                Ordering::Equal => return Some(node),
+                Ordering::Less => node = self.left(node)?,
+                Ordering::Greater => node = self.right(node)?,
+            }
+        }
+    }
+
+    // 同じkeyがすでに存在する場合は、容量に関係なくvalueを上書きして古いvalueを返す
+    // 新しいkeyを置く枠がない場合は、keyとvalueをCapacityErrorに入れて返す
+    pub fn insert(&mut self, key: K, value: V) -> Result<Option<V>, CapacityError<K, V>> {
+        let mut parent = None;
+        let mut current_node = self.root;
+        let mut ordering = Ordering::Equal;
+        while let Some(current) = current_node {
+            parent = Some(current);
+            ordering = key.cmp(&self.node(current).key);
+            current_node = match ordering {
+                Ordering::Less => self.left(current),
+                Ordering::Greater => self.right(current),
+                Ordering::Equal => return Ok(Some(mem::replace(&mut self.node_mut(current).value, value))),
+            };
+        }
+        let node = StaticNode {
+            key,
+            value,
+            parent_color: ParentColor::new(parent, Color::Red),
+            left: None,
+            right: None,
+        };
+        let node = match self.allocate(node) {
+            Ok(node) => node,
+            Err(node) => return Err(CapacityError { key: node.key, value: node.value }),
+        };
+        match parent {
+            None => self.root = Some(node),
+            Some(parent) if ordering == Ordering::Less => self.set_left(parent, Some(node)),
+            Some(parent) => self.set_right(parent, Some(node)),
+        }
+        self.len += 1;
+        self.change_structure(node);
+        Ok(None)
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+        where K: Borrow<Q>, Q: Ord + ?Sized {
+        self.search(key).map(|node| &self.node(node).value)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+        where K: Borrow<Q>, Q: Ord + ?Sized {
+        let node = self.search(key)?;
+        Some(&mut self.node_mut(node).value)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+        where K: Borrow<Q>, Q: Ord + ?Sized {
+        self.search(key).is_some()
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+        where K: Borrow<Q>, Q: Ord + ?Sized {
+        let node = self.search(key)?;
+        self.delete_node(node);
+        self.len -= 1;
+        Some(self.deallocate(node).value)
+    }
+
+    pub fn first_key_value(&self) -> Option<(&K, &V)> {
+        let node = self.node(self.minimum(self.root?));
+        Some((&node.key, &node.value))
+    }
+
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        let node = self.node(self.maximum()?);
+        Some((&node.key, &node.value))
+    }
+
+    fn maximum(&self) -> Option<NodeId> {
+        let mut node = self.root?;
+        while let Some(right) = self.right(node) {
+            node = right;
+        }
+        Some(node)
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V, N> {
+        Iter {
+            tree: self,
+            front: self.root.map(|root| self.minimum(root)),
+            back: self.maximum(),
+        }
+    }
+
+    // 全ての枠を空にして、使っていない状態に戻す
+    pub fn clear(&mut self) {
+        for slot in self.nodes[..self.used].iter_mut() {
+            *slot = StaticSlot::Vacant(None);
+        }
+        self.free = None;
+        self.used = 0;
+        self.root = None;
+        self.len = 0;
+    }
+}
+
+impl<K: Ord + Clone + Debug, V, const N: usize> Links for StaticRBTree<K, V, N> {
+    type Node = NodeId;
+
+    #[inline]
+    fn root(&self) -> Option<NodeId> {
+        self.root
+    }
+
+    #[inline]
+    fn set_root(&mut self, root: Option<NodeId>) {
+        self.root = root;
+    }
+
+    #[inline]
+    fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.node(id).parent_color.parent()
+    }
+
+    #[inline]
+    fn set_parent(&mut self, id: NodeId, parent: Option<NodeId>) {
+        let node = self.node_mut(id);
+        node.parent_color = ParentColor::new(parent, node.parent_color.color());
+    }
+
+    #[inline]
+    fn left(&self, id: NodeId) -> Option<NodeId> {
+        self.node(id).left
+    }
+
+    #[inline]
+    fn set_left(&mut self, id: NodeId, left: Option<NodeId>) {
+        self.node_mut(id).left = left;
+    }
+
+    #[inline]
+    fn right(&self, id: NodeId) -> Option<NodeId> {
+        self.node(id).right
+    }
+
+    #[inline]
+    fn set_right(&mut self, id: NodeId, right: Option<NodeId>) {
+        self.node_mut(id).right = right;
+    }
+
+    #[inline]
+    fn color(&self, id: NodeId) -> Color {
+        self.node(id).parent_color.color()
+    }
+
+    #[inline]
+    fn set_color(&mut self, id: NodeId, color: Color) {
+        let node = self.node_mut(id);
+        node.parent_color = ParentColor::new(node.parent_color.parent(), color);
+    }
+}
+
+impl<K: Ord + Clone + Debug, V, const N: usize> Default for StaticRBTree<K, V, N> {
+    fn default() -> StaticRBTree<K, V, N> {
+        StaticRBTree::new()
+    }
+}
+
+impl<K: Ord + Clone + Debug, V: Debug, const N: usize> Debug for StaticRBTree<K, V, N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+pub struct Iter<'a, K: Ord + Clone + Debug, V, const N: usize> {
+    tree: &'a StaticRBTree<K, V, N>,
+    front: Option<NodeId>,
+    back: Option<NodeId>,
+}
+
+impl<'a, K: Ord + Clone + Debug, V, const N: usize> Iterator for Iter<'a, K, V, N> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        let tree = self.tree;
+        let node = self.front?;
+        // 前後から辿ってきたノードが出会ったら終わり
+        if self.front == self.back {
+            self.front = None;
+            self.back = None;
+        } else {
+            self.front = tree.successor(node);
+        }
+        let node = tree.node(node);
+        Some((&node.key, &node.value))
+    }
+}
+
+impl<'a, K: Ord + Clone + Debug, V, const N: usize> DoubleEndedIterator for Iter<'a, K, V, N> {
+    fn next_back(&mut self) -> Option<(&'a K, &'a V)> {
+        let tree = self.tree;
+        let node = self.back?;
+        if self.front == self.back {
+            self.front = None;
+            self.back = None;
+        } else {
+            self.back = tree.predecessor(node);
+        }
+        let node = tree.node(node);
+        Some((&node.key, &node.value))
+    }
+}
+
+impl<'a, K: Ord + Clone + Debug, V, const N: usize> IntoIterator for &'a StaticRBTree<K, V, N> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V, N>;
+
+    fn into_iter(self) -> Iter<'a, K, V, N> {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    // 赤黒木の性質を満たしていることを確かめて、黒高さを返す
+    fn black_height<K: Ord + Clone + Debug, V, const N: usize>(tree: &StaticRBTree<K, V, N>, node: Option<NodeId>, parent: Option<NodeId>) -> usize {
+        let node = match node {
+            Some(node) => node,
+            None => return 1,
+        };
+        assert_eq!(parent, tree.parent(node));
+        if tree.is_red(Some(node)) {
+            assert!(tree.is_black(tree.left(node)));
+            assert!(tree.is_black(tree.right(node)));
+        }
+        let left = black_height(tree, tree.left(node), Some(node));
+        let right = black_height(tree, tree.right(node), Some(node));
+        assert_eq!(left, right);
+        left + if tree.is_black(Some(node)) { 1 } else { 0 }
+    }
+
+    // 定数の中でも作れる
+    static EMPTY: StaticRBTree<u32, u32, 4> = StaticRBTree::new();
+
+    #[test]
+    fn static_rb_tree() {
+        assert!(EMPTY.is_empty());
+        assert_eq!(4, EMPTY.capacity());
+
+        let mut tree: StaticRBTree<u32, &str, 4> = StaticRBTree::new();
+        assert_eq!(Ok(None), tree.insert(3, "c"));
+        assert_eq!(Ok(None), tree.insert(1, "a"));
+        assert_eq!(Ok(None), tree.insert(4, "d"));
+        assert_eq!(Ok(None), tree.insert(2, "b"));
+        assert!(tree.is_full());
+        // 容量いっぱいでも既存のkeyは上書きできる
+        assert_eq!(Ok(Some("d")), tree.insert(4, "D"));
+        let error = tree.insert(5, "e").unwrap_err();
+        assert_eq!((5, "e"), error.into_inner());
+        assert_eq!(vec![(&1, &"a"), (&2, &"b"), (&3, &"c"), (&4, &"D")], tree.iter().collect::<Vec<_>>());
+        assert_eq!(Some((&4, &"D")), tree.last_key_value());
+
+        // 削除で空いた枠を使い回す
+        assert_eq!(Some("b"), tree.remove(&2));
+        assert_eq!(None, tree.remove(&2));
+        assert_eq!(Ok(None), tree.insert(5, "e"));
+        assert_eq!(4, tree.used);
+        *tree.get_mut(&5).unwrap() = "E";
+        assert_eq!(Some(&"E"), tree.get(&5));
+        assert_eq!("{1: \"a\", 3: \"c\", 4: \"D\", 5: \"E\"}", format!("{:?}", tree));
+
+        tree.clear();
+        assert!(tree.is_empty());
+        assert_eq!(None, tree.first_key_value());
+        assert_eq!(Ok(None), tree.insert(0, "z"));
+    }
+
+    #[test]
+    fn static_rb_tree_random() {
+        let mut tree: StaticRBTree<u64, u64, 64> = StaticRBTree::new();
+        let mut expected = BTreeMap::new();
+        let mut seed: u64 = 3;
+        for _ in 0..2000 {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let key = (seed >> 33) % 100;
+            if (seed >> 20) % 3 == 0 {
+                assert_eq!(expected.remove(&key), tree.remove(&key));
+            } else if expected.len() < 64 || expected.contains_key(&key) {
+                assert_eq!(Ok(expected.insert(key, seed)), tree.insert(key, seed));
+            } else {
+                assert!(tree.insert(key, seed).is_err());
+            }
+            assert_eq!(expected.len(), tree.len());
+        }
+        assert!(tree.is_black(tree.root));
+        black_height(&tree, tree.root, None);
+        assert!(expected.iter().eq(tree.iter()));
+        assert!(expected.iter().rev().eq(tree.iter().rev()));
+    }
+}
@@ -0,0 +1,229 @@
+use alloc::vec::Vec;
+use alloc::vec;
+use core::fmt::Debug;
+use core::ops::{Bound, Range};
+
+use super::{Augment, Links, NodeId, NoUpdate, RBTree};
+
+// 部分木の区間の終了位置の最大値を集約する
+pub struct MaxEnd;
+
+impl<T: Ord + Clone, V> Augment<(T, T), V> for MaxEnd {
+    type Summary = Option<T>;
+    type Update = ();
+
+    fn empty() -> Option<T> { None }
+
+    fn combine(left: &Option<T>, node: (&(T, T), &V), right: &Option<T>) -> Option<T> {
+        let end = Some(node.0 .1.clone());
+        left.clone().max(end).max(right.clone())
+    }
+
+    const HAS_UPDATE: bool = false;
+}
+
+// apply_rangeを使わないので、IntervalTreeもSyncになる
+unsafe impl<T: Ord + Clone, V> NoUpdate<(T, T), V> for MaxEnd {}
+
+// 半開区間[start, end)をkeyにするRBTree
+// (start, end)の順に並べて、部分木の終了位置の最大値を使って重なる区間を探す
+// 同じ区間は別々のノードとして挿入した順に並ぶ
+pub struct IntervalTree<T: Ord + Clone + Debug, V> {
+    tree: RBTree<(T, T), V, MaxEnd>,
+}
+
+impl<T: Ord + Clone + Debug, V> IntervalTree<T, V> {
+    pub fn new() -> IntervalTree<T, V> {
+        IntervalTree {
+            tree: RBTree::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.len() == 0
+    }
+
+    pub fn insert(&mut self, range: Range<T>, value: V) {
+        assert!(range.start < range.end, "interval start must be less than end. start: {:?}, end: {:?}", range.start, range.end);
+        self.tree.insert_multi((range.start, range.end), value);
+    }
+
+    // rangeと同じ区間のうち、最初に挿入したものを取り除く
+    pub fn remove(&mut self, range: &Range<T>) -> Option<V> {
+        let key = (range.start.clone(), range.end.clone());
+        let node = self.tree.lower_bound(Bound::Included(&key))?;
+        if self.tree.node(node).key != key { return None; }
+        Some(self.tree.remove_node(node).1)
+    }
+
+    // 開始位置の昇順に全ての区間を返す
+    pub fn iter(&self) -> impl Iterator<Item = (Range<T>, &V)> + '_ {
+        self.tree.iter().map(|((start, end), value)| (start.clone()..end.clone(), value))
+    }
+
+    // rangeと重なる区間を開始位置の昇順に返す
+    pub fn overlapping(&self, range: Range<T>) -> Overlapping<'_, T, V> {
+        Overlapping::new(&self.tree, range.start, Bound::Excluded(range.end))
+    }
+
+    // pointを含む区間を開始位置の昇順に返す
+    pub fn stabbing(&self, point: T) -> Overlapping<'_, T, V> {
+        Overlapping::new(&self.tree, point.clone(), Bound::Included(point))
+    }
+
+    // rangeと重なる区間が存在するかを返す
+    // 左の部分木の終了位置の最大値がrangeの開始位置より大きければ、左に重なる区間があるか、右にも無いかのどちらかなので、
+    // 1本のパスを降りるだけで済む
+    pub fn any_overlap(&self, range: Range<T>) -> bool {
+        let mut node = self.tree.root;
+        while let Some(current_node) = node {
+            let current_node = self.tree.node(current_node);
+            let (start, end) = &current_node.key;
+            if *start < range.end && range.start < *end { return true; }
+            let left_max_end = self.tree.summary(current_node.left);
+            node = if left_max_end.is_some_and(|max_end| range.start < max_end) {
+                current_node.left
+            } else {
+                current_node.right
+            };
+        }
+        false
+    }
+}
+
+impl<T: Ord + Clone + Debug, V> Default for IntervalTree<T, V> {
+    fn default() -> IntervalTree<T, V> {
+        IntervalTree::new()
+    }
+}
+
+// 重なる区間を返すイテレータ
+// 終了位置の最大値がstart以下の部分木と、開始位置がendを超えたノードより後ろは辿らない
+pub struct Overlapping<'a, T: Ord + Clone + Debug, V> {
+    // 中間順でまだ返していないノード
+    stack: Vec<NodeId>,
+    tree: &'a RBTree<(T, T), V, MaxEnd>,
+    start: T,
+    end: Bound<T>,
+}
+
+impl<'a, T: Ord + Clone + Debug, V> Overlapping<'a, T, V> {
+    fn new(tree: &'a RBTree<(T, T), V, MaxEnd>, start: T, end: Bound<T>) -> Overlapping<'a, T, V> {
+        let mut overlapping = Overlapping { stack: vec![], tree, start, end };
+        overlapping.push_left(tree.root);
+        overlapping
+    }
+
+    // 重なる区間を含む可能性のある部分木について、左端まで降りながらノードを積む
+    fn push_left(&mut self, node: Option<NodeId>) {
+        let mut node = node;
+        while let Some(current_node) = node {
+            if self.tree.node(current_node).summary().as_ref().map_or(true, |max_end| *max_end <= self.start) { break; }
+            self.stack.push(current_node);
+            node = self.tree.left(current_node);
+        }
+    }
+}
+
+impl<'a, T: Ord + Clone + Debug, V> Iterator for Overlapping<'a, T, V> {
+    type Item = (Range<T>, &'a V);
+
+    fn next(&mut self) -> Option<(Range<T>, &'a V)> {
+        while let Some(node) = self.stack.pop() {
+            let node = self.tree.node(node);
+            let (start, end) = &node.key;
+            let before_end = match &self.end {
+                Bound::Included(point) => start <= point,
+                Bound::Excluded(end) => start < end,
+                Bound::Unbounded => true,
+            };
+            // これより後ろの区間は全て開始位置が範囲を超えている
+            if !before_end {
+                self.stack.clear();
+                return None;
+            }
+            self.push_left(node.right);
+            if self.start < *end {
+                return Some((start.clone()..end.clone(), node.value()));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn intervals() -> Vec<Range<usize>> {
+        // 長さがばらばらの区間を、開始位置の順序がばらばらになるように作る
+        (0..200).map(|i| {
+            let start = i * 37 % 500;
+            start..start + i % 23 + 1
+        }).collect()
+    }
+
+    #[test]
+    fn overlapping_stabbing() {
+        let mut tree: IntervalTree<usize, usize> = IntervalTree::new();
+        let intervals = intervals();
+        for (i, range) in intervals.iter().enumerate() {
+            tree.insert(range.clone(), i);
+        }
+        assert_eq!(200, tree.len());
+        for query_start in (0..520).step_by(13) {
+            for length in [1, 5, 40] {
+                let query = query_start..query_start + length;
+                let mut expected: Vec<(Range<usize>, usize)> = intervals.iter().cloned().enumerate()
+                    .filter(|(_, range)| range.start < query.end && query.start < range.end)
+                    .map(|(i, range)| (range, i))
+                    .collect();
+                expected.sort_by_key(|(range, _)| (range.start, range.end));
+                assert_eq!(expected, tree.overlapping(query.clone()).map(|(range, i)| (range, *i)).collect::<Vec<_>>());
+                assert_eq!(!expected.is_empty(), tree.any_overlap(query));
+            }
+            let expected = intervals.iter().filter(|range| range.contains(&query_start)).count();
+            assert_eq!(expected, tree.stabbing(query_start).count());
+        }
+    }
+
+    #[test]
+    fn remove() {
+        let mut tree: IntervalTree<usize, &str> = IntervalTree::new();
+        tree.insert(10..20, "a");
+        tree.insert(15..30, "b");
+        tree.insert(10..20, "c");
+        tree.insert(40..50, "d");
+        assert_eq!(vec![(10..20, &"a"), (10..20, &"c"), (15..30, &"b")], tree.stabbing(16).collect::<Vec<_>>());
+
+        assert_eq!(Some("a"), tree.remove(&(10..20)));
+        assert_eq!(None, tree.remove(&(10..25)));
+        assert_eq!(Some("b"), tree.remove(&(15..30)));
+        assert_eq!(vec![(10..20, &"c")], tree.overlapping(0..100).take(1).collect::<Vec<_>>());
+        assert!(!tree.any_overlap(20..40));
+        assert!(tree.any_overlap(19..40));
+        assert!(tree.any_overlap(20..41));
+        assert_eq!(2, tree.len());
+
+        // 削除で回転しても終了位置の最大値が保たれる
+        let mut tree: IntervalTree<usize, usize> = IntervalTree::new();
+        let intervals = intervals();
+        for (i, range) in intervals.iter().enumerate() {
+            tree.insert(range.clone(), i);
+        }
+        for (i, range) in intervals.iter().enumerate().filter(|(i, _)| i % 3 != 0) {
+            assert_eq!(Some(i), tree.remove(range));
+        }
+        for point in 0..520 {
+            let expected = intervals.iter().enumerate()
+                .filter(|(i, range)| i % 3 == 0 && range.contains(&point))
+                .count();
+            assert_eq!(expected, tree.stabbing(point).count());
+            assert_eq!(expected > 0, tree.any_overlap(point..point + 1));
+        }
+    }
+}
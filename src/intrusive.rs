@@ -0,0 +1,518 @@
+use core::borrow::Borrow;
+use core::cell::Cell;
+use core::cmp::Ordering;
+use core::fmt;
+use core::fmt::{Debug, Formatter};
+use core::marker::PhantomData;
+use core::ptr::NonNull;
+
+use super::{Color, Links};
+
+// 利用者の構造体に埋め込んで、IntrusiveRBTreeから辿れるようにするリンク
+// Linuxカーネルのrb_nodeと同じく、木はノードを確保せずにこのリンク同士を繋ぐ
+// 1つの構造体に複数のRBLinkを持たせれば、同じ値を複数の木に同時に入れられる
+pub struct RBLink {
+    parent: Cell<Option<NonNull<RBLink>>>,
+    left: Cell<Option<NonNull<RBLink>>>,
+    right: Cell<Option<NonNull<RBLink>>>,
+    // どの木にも繋がっていない場合はNone
+    color: Cell<Option<Color>>,
+}
+
+impl RBLink {
+    pub const fn new() -> RBLink {
+        RBLink {
+            parent: Cell::new(None),
+            left: Cell::new(None),
+            right: Cell::new(None),
+            color: Cell::new(None),
+        }
+    }
+
+    pub fn is_linked(&self) -> bool {
+        self.color.get().is_some()
+    }
+
+    fn unlink(&self) {
+        self.parent.set(None);
+        self.left.set(None);
+        self.right.set(None);
+        self.color.set(None);
+    }
+}
+
+impl Default for RBLink {
+    fn default() -> RBLink {
+        RBLink::new()
+    }
+}
+
+impl Debug for RBLink {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RBLink").field("linked", &self.is_linked()).finish()
+    }
+}
+
+// 値とその中に埋め込まれたRBLinkを対応させる。普通はintrusive_adapter!で実装する
+// 実装する側が守ること(unsafe traitなのはこのため):
+// - linkは、同じ値に対して常にその値の中にある同じRBLinkを返すこと
+// - valueは、linkが返したRBLinkへのポインタから、そのRBLinkを含む値へのポインタを戻すこと(container_of)
+//   木はこのポインタから値への参照を作るので、違う値や値の外を指すと未定義動作になる
+// - keyは、値が木に繋がっている間は他の値のkeyとの順序が変わらないこと
+//   木はinsertの時のkeyの順序で値を並べるので、順序が変わると並びが崩れて、first/lastや順に辿った結果が正しくなくなる
+//   (リンクは値の中にあるので、並びが崩れても未定義動作にはならない)
+#[allow(clippy::missing_safety_doc)]
+pub unsafe trait Adapter {
+    type Value;
+    type Key: Ord + ?Sized;
+
+    fn link(value: &Self::Value) -> &RBLink;
+
+    // RBLinkへのポインタから、それを含む値へのポインタを戻す
+    // 呼ぶ側が守ること: linkはSelf::linkが返したRBLinkを指していること
+    #[allow(clippy::missing_safety_doc)]
+    unsafe fn value(link: NonNull<RBLink>) -> *const Self::Value;
+
+    // 繋がっている間は順序が変わらないこと(上の約束を参照)
+    fn key(value: &Self::Value) -> &Self::Key;
+}
+
+// intrusive_adapter!(ByDeadline = Timer { by_deadline } => u64, |timer| &timer.deadline);
+// のように、値の型とRBLinkのフィールド、keyの取り出し方からAdapterを実装する
+#[macro_export]
+macro_rules! intrusive_adapter {
+    ($adapter:ident = $value:ty { $link:ident } => $key:ty, |$v:ident| $get_key:expr) => {
+        struct $adapter;
+
+        unsafe impl $crate::intrusive::Adapter for $adapter {
+            type Value = $value;
+            type Key = $key;
+
+            fn link(value: &$value) -> &$crate::intrusive::RBLink {
+                &value.$link
+            }
+
+            unsafe fn value(link: ::core::ptr::NonNull<$crate::intrusive::RBLink>) -> *const $value {
+                link.as_ptr().cast::<u8>().sub(::core::mem::offset_of!($value, $link)).cast::<$value>()
+            }
+
+            fn key($v: &$value) -> &$key {
+                $get_key
+            }
+        }
+    };
+}
+
+// 利用者の値を確保せずに繋ぐ赤黒木
+// 値は'aの間借りるので、木が生きている間に動かされたり解放されたりすることはない
+// 借りるのは&なので、keyの他の部分を書き換える場合はCellなどを使う
+// 木がなくなった後に値が残っていても大丈夫なのは、Dropで全ての値のリンクを外すから。なのでinsertはunsafeになっている
+pub struct IntrusiveRBTree<'a, A: Adapter> {
+    root: Option<NonNull<RBLink>>,
+    len: usize,
+    marker: PhantomData<&'a A::Value>,
+}
+
+impl<'a, A: Adapter> IntrusiveRBTree<'a, A> {
+    pub fn new() -> IntrusiveRBTree<'a, A> {
+        IntrusiveRBTree {
+            root: None,
+            len: 0,
+            marker: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // 木に繋がっているリンクは全て'aの間借りている値の中にある
+    #[inline]
+    fn link(&self, node: NonNull<RBLink>) -> &'a RBLink {
+        unsafe { &*node.as_ptr() }
+    }
+
+    #[inline]
+    fn value(&self, node: NonNull<RBLink>) -> &'a A::Value {
+        unsafe { &*A::value(node) }
+    }
+
+    // Adapter::valueで値全体に戻れるように、値への参照からリンクを指すポインタを作る
+    fn node_of(value: &A::Value) -> NonNull<RBLink> {
+        let base = value as *const A::Value;
+        let offset = A::link(value) as *const RBLink as usize - base as usize;
+        NonNull::new(base.cast::<u8>().wrapping_add(offset) as *mut RBLink).unwrap()
+    }
+
+    // 値を木に繋ぐ。同じkeyの値がすでに存在する場合は、その後ろに繋ぐ
+    // 値のリンクがすでにどこかの木に繋がっている場合はpanicする
+    // 呼ぶ側が守ること(unsafeなのはこのため):
+    // 値が繋がっている間は、木をmem::forgetやRcの循環などでDropせずに捨ててはいけない
+    // 借用が終わるとその値は解放できるようになるが、木のDropが走らないと、同じ木の他の値のリンクは解放された値を指したまま残る
+    // その後でcontainsやremoveなどがそのリンクを辿ると、解放されたメモリを読む
+    #[allow(clippy::missing_safety_doc)]
+    pub unsafe fn insert(&mut self, value: &'a A::Value) {
+        let link = A::link(value);
+        assert!(!link.is_linked(), "RBLink is already linked to a rb-tree");
+        let node = Self::node_of(value);
+        let key = A::key(value);
+        let mut parent = None;
+        let mut current_node = self.root;
+        let mut is_left = false;
+        while let Some(current) = current_node {
+            parent = Some(current);
+            is_left = key < A::key(self.value(current));
+            current_node = if is_left { self.left(current) } else { self.right(current) };
+        }
+        link.parent.set(parent);
+        link.color.set(Some(Color::Red));
+        match parent {
+            None => self.root = Some(node),
+            Some(parent) if is_left => self.set_left(parent, Some(node)),
+            Some(parent) => self.set_right(parent, Some(node)),
+        }
+        self.len += 1;
+        self.change_structure(node);
+    }
+
+    // この木に繋がっている値かどうかを、根まで登って確かめる
+    pub fn contains(&self, value: &A::Value) -> bool {
+        let link = A::link(value);
+        if !link.is_linked() { return false; }
+        let mut node = Self::node_of(value);
+        while let Some(parent) = self.parent(node) {
+            node = parent;
+        }
+        self.root == Some(node)
+    }
+
+    // 値を木から外す。この木に繋がっていなかった場合はfalseを返す
+    pub fn remove(&mut self, value: &A::Value) -> bool {
+        if !self.contains(value) { return false; }
+        self.delete_node(Self::node_of(value));
+        A::link(value).unlink();
+        self.len -= 1;
+        true
+    }
+
+    // keyを持つ値のうち、最初に挿入したものを返す
+    pub fn find<Q>(&self, key: &Q) -> Option<&'a A::Value>
+        where A::Key: Borrow<Q>, Q: Ord + ?Sized {
+        let mut found = None;
+        let mut current_node = self.root;
+        while let Some(current) = current_node {
+            match key.cmp(A::key(self.value(current)).borrow()) {
+                Ordering::Less => current_node = self.left(current),
+                Ordering::Greater => current_node = self.right(current),
+                Ordering::Equal => {
+                    found = Some(current);
+                    current_node = self.left(current);
+                },
+            }
+        }
+        found.map(|node| self.value(node))
+    }
+
+    fn first_node(&self) -> Option<NonNull<RBLink>> {
+        self.root.map(|root| self.minimum(root))
+    }
+
+    fn last_node(&self) -> Option<NonNull<RBLink>> {
+        let mut node = self.root?;
+        while let Some(right) = self.right(node) {
+            node = right;
+        }
+        Some(node)
+    }
+
+    pub fn first(&self) -> Option<&'a A::Value> {
+        self.first_node().map(|node| self.value(node))
+    }
+
+    pub fn last(&self) -> Option<&'a A::Value> {
+        self.last_node().map(|node| self.value(node))
+    }
+
+    pub fn pop_first(&mut self) -> Option<&'a A::Value> {
+        let first = self.first()?;
+        self.remove(first);
+        Some(first)
+    }
+
+    pub fn iter(&self) -> Iter<'_, 'a, A> {
+        Iter {
+            tree: self,
+            front: self.first_node(),
+            back: self.last_node(),
+        }
+    }
+
+    // 全ての値のリンクを外す。successorは外したリンクを辿ってしまうので、葉から順に外す
+    pub fn clear(&mut self) {
+        let mut current_node = self.root.take();
+        while let Some(current) = current_node {
+            if let Some(left) = self.left(current) {
+                current_node = Some(left);
+                continue;
+            }
+            if let Some(right) = self.right(current) {
+                current_node = Some(right);
+                continue;
+            }
+            let parent = self.parent(current);
+            if let Some(parent) = parent {
+                if self.left(parent) == Some(current) {
+                    self.set_left(parent, None);
+                } else {
+                    self.set_right(parent, None);
+                }
+            }
+            self.link(current).unlink();
+            current_node = parent;
+        }
+        self.len = 0;
+    }
+}
+
+impl<'a, A: Adapter> Links for IntrusiveRBTree<'a, A> {
+    type Node = NonNull<RBLink>;
+
+    #[inline]
+    fn root(&self) -> Option<NonNull<RBLink>> {
+        self.root
+    }
+
+    #[inline]
+    fn set_root(&mut self, root: Option<NonNull<RBLink>>) {
+        self.root = root;
+    }
+
+    #[inline]
+    fn parent(&self, node: NonNull<RBLink>) -> Option<NonNull<RBLink>> {
+        self.link(node).parent.get()
+    }
+
+    #[inline]
+    fn set_parent(&mut self, node: NonNull<RBLink>, parent: Option<NonNull<RBLink>>) {
+        self.link(node).parent.set(parent);
+    }
+
+    #[inline]
+    fn left(&self, node: NonNull<RBLink>) -> Option<NonNull<RBLink>> {
+        self.link(node).left.get()
+    }
+
+    #[inline]
+    fn set_left(&mut self, node: NonNull<RBLink>, left: Option<NonNull<RBLink>>) {
+        self.link(node).left.set(left);
+    }
+
+    #[inline]
+    fn right(&self, node: NonNull<RBLink>) -> Option<NonNull<RBLink>> {
+        self.link(node).right.get()
+    }
+
+    #[inline]
+    fn set_right(&mut self, node: NonNull<RBLink>, right: Option<NonNull<RBLink>>) {
+        self.link(node).right.set(right);
+    }
+
+    #[inline]
+    fn color(&self, node: NonNull<RBLink>) -> Color {
+        self.link(node).color.get().expect("RBLink in a rb-tree is not linked")
+    }
+
+    #[inline]
+    fn set_color(&mut self, node: NonNull<RBLink>, color: Color) {
+        self.link(node).color.set(Some(color));
+    }
+}
+
+// 木が先になくなる場合も、値を他の木に入れ直せるようにリンクを外しておく
+impl<'a, A: Adapter> Drop for IntrusiveRBTree<'a, A> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<'a, A: Adapter> Default for IntrusiveRBTree<'a, A> {
+    fn default() -> IntrusiveRBTree<'a, A> {
+        IntrusiveRBTree::new()
+    }
+}
+
+impl<'a, A: Adapter> Debug for IntrusiveRBTree<'a, A>
+    where A::Value: Debug {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+pub struct Iter<'t, 'a, A: Adapter> {
+    tree: &'t IntrusiveRBTree<'a, A>,
+    front: Option<NonNull<RBLink>>,
+    back: Option<NonNull<RBLink>>,
+}
+
+impl<'t, 'a, A: Adapter> Iterator for Iter<'t, 'a, A> {
+    type Item = &'a A::Value;
+
+    fn next(&mut self) -> Option<&'a A::Value> {
+        let node = self.front?;
+        // 前後から辿ってきたノードが出会ったら終わり
+        if self.front == self.back {
+            self.front = None;
+            self.back = None;
+        } else {
+            self.front = self.tree.successor(node);
+        }
+        Some(self.tree.value(node))
+    }
+}
+
+impl<'t, 'a, A: Adapter> DoubleEndedIterator for Iter<'t, 'a, A> {
+    fn next_back(&mut self) -> Option<&'a A::Value> {
+        let node = self.back?;
+        if self.front == self.back {
+            self.front = None;
+            self.back = None;
+        } else {
+            self.back = self.tree.predecessor(node);
+        }
+        Some(self.tree.value(node))
+    }
+}
+
+impl<'t, 'a, A: Adapter> IntoIterator for &'t IntrusiveRBTree<'a, A> {
+    type Item = &'a A::Value;
+    type IntoIter = Iter<'t, 'a, A>;
+
+    fn into_iter(self) -> Iter<'t, 'a, A> {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct Timer {
+        id: usize,
+        deadline: u64,
+        fired: Cell<bool>,
+        by_deadline: RBLink,
+        by_id: RBLink,
+    }
+
+    impl Timer {
+        fn new(id: usize, deadline: u64) -> Timer {
+            Timer { id, deadline, fired: Cell::new(false), by_deadline: RBLink::new(), by_id: RBLink::new() }
+        }
+    }
+
+    intrusive_adapter!(ByDeadline = Timer { by_deadline } => u64, |timer| &timer.deadline);
+    intrusive_adapter!(ById = Timer { by_id } => usize, |timer| &timer.id);
+
+    // 赤黒木の性質を満たしていて、keyが昇順に並んでいることを確かめる
+    fn assert_intrusive_tree<A: Adapter>(tree: &IntrusiveRBTree<'_, A>)
+        where A::Key: Debug {
+        fn black_height<A: Adapter>(tree: &IntrusiveRBTree<'_, A>, node: Option<NonNull<RBLink>>, parent: Option<NonNull<RBLink>>) -> usize {
+            let node = match node {
+                Some(node) => node,
+                None => return 1,
+            };
+            assert_eq!(parent, tree.parent(node));
+            if tree.is_red(Some(node)) {
+                assert!(tree.is_black(tree.left(node)));
+                assert!(tree.is_black(tree.right(node)));
+            }
+            let left = black_height(tree, tree.left(node), Some(node));
+            let right = black_height(tree, tree.right(node), Some(node));
+            assert_eq!(left, right);
+            left + if tree.is_black(Some(node)) { 1 } else { 0 }
+        }
+        assert!(tree.is_black(tree.root));
+        black_height(tree, tree.root, None);
+        let keys = tree.iter().map(A::key).collect::<Vec<_>>();
+        assert_eq!(tree.len(), keys.len());
+        assert!(keys.windows(2).all(|keys| keys[0] <= keys[1]));
+        let mut reversed = tree.iter().rev().map(A::key).collect::<Vec<_>>();
+        reversed.reverse();
+        assert_eq!(keys, reversed);
+    }
+
+    #[test]
+    fn intrusive() {
+        let timers = (0..200).map(|id| Timer::new(id, (id as u64 * 37) % 50)).collect::<Vec<_>>();
+        let mut by_deadline: IntrusiveRBTree<ByDeadline> = IntrusiveRBTree::new();
+        let mut by_id: IntrusiveRBTree<ById> = IntrusiveRBTree::new();
+        // 木はtimersより先にDropされる
+        for timer in &timers {
+            unsafe {
+                by_deadline.insert(timer);
+                by_id.insert(timer);
+            }
+        }
+        assert_intrusive_tree(&by_deadline);
+        assert_intrusive_tree(&by_id);
+        assert_eq!(200, by_deadline.len());
+        // 同じdeadlineの値は挿入した順に並ぶ
+        assert_eq!(vec![0, 50, 100, 150], by_deadline.iter().take(4).map(|timer| timer.id).collect::<Vec<_>>());
+        assert_eq!(Some(13), by_deadline.find(&31).map(|timer| timer.id));
+        assert_eq!(Some(199), by_id.last().map(|timer| timer.id));
+
+        // 片方の木から外しても、もう片方の木には残る
+        let mut seed: u64 = 1;
+        for _ in 0..100 {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let timer = &timers[((seed >> 33) % 200) as usize];
+            assert_eq!(by_deadline.contains(timer), by_deadline.remove(timer));
+            assert!(!by_deadline.contains(timer));
+            assert!(by_id.contains(timer));
+        }
+        assert_intrusive_tree(&by_deadline);
+        assert_intrusive_tree(&by_id);
+        assert_eq!(200, by_id.len());
+        assert_eq!(timers.iter().filter(|timer| timer.by_deadline.is_linked()).count(), by_deadline.len());
+
+        // 期限の早い順に取り出して、idの木からも外す
+        while let Some(timer) = by_deadline.pop_first() {
+            if timer.deadline >= 10 { break; }
+            timer.fired.set(true);
+            assert!(by_id.remove(timer));
+        }
+        assert!(by_id.iter().all(|timer| !timer.fired.get()));
+        assert_intrusive_tree(&by_id);
+
+        // 外した値は入れ直せる
+        let first = &timers[0];
+        assert!(!first.by_deadline.is_linked());
+        unsafe { by_deadline.insert(first); }
+        assert_eq!(Some(0), by_deadline.first().map(|timer| timer.id));
+        assert_intrusive_tree(&by_deadline);
+
+        // 木をなくすと全ての値のリンクが外れる
+        by_id.clear();
+        assert!(by_id.is_empty());
+        assert!(timers.iter().all(|timer| !timer.by_id.is_linked()));
+        drop(by_deadline);
+        assert!(timers.iter().all(|timer| !timer.by_deadline.is_linked()));
+    }
+
+    #[test]
+    #[should_panic(expected = "already linked")]
+    fn intrusive_insert_twice() {
+        let timer = Timer::new(0, 0);
+        let mut tree: IntrusiveRBTree<ByDeadline> = IntrusiveRBTree::new();
+        let mut other: IntrusiveRBTree<ByDeadline> = IntrusiveRBTree::new();
+        unsafe {
+            tree.insert(&timer);
+            other.insert(&timer);
+        }
+    }
+}
@@ -0,0 +1,3779 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+#[cfg(doctest)]
+mod compile_fail;
+pub mod cow;
+pub mod entry;
+pub mod fixed;
+pub mod interval;
+pub mod intrusive;
+pub mod multimap;
+pub mod persistent;
+pub mod range_map;
+pub mod set;
+pub mod versioned;
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use alloc::vec;
+use core::borrow::Borrow;
+use core::cell::UnsafeCell;
+use core::cmp::Ordering;
+use core::convert::TryFrom;
+use core::fmt;
+use core::fmt::{Debug, Formatter, Error};
+use core::hash::{Hash, Hasher};
+use core::iter::{FromIterator, FusedIterator};
+use core::marker::PhantomData;
+use core::mem;
+use core::num::NonZeroU32;
+use core::ops::{Bound, Index, RangeBounds};
+#[cfg(test)]
+use core::ops::Deref;
+
+
+// 部分木ごとに持たせる集約値(summary)の計算方法
+// combineは、左の部分木のsummary、自分のkeyとvalue、右の部分木のsummaryから自分の部分木のsummaryを計算する
+// 回転や範囲の集約で区切り方が変わっても同じ結果になるように、
+// combine(&combine(a, x, b), y, c) == combine(a, x, &combine(b, y, c)) を満たす必要がある
+pub trait Augment<K, V> {
+    type Summary: Clone;
+
+    // 空の部分木のsummary
+    fn empty() -> Self::Summary;
+
+    fn combine(left: &Self::Summary, node: (&K, &V), right: &Self::Summary) -> Self::Summary;
+
+    // apply_rangeで範囲にまとめて適用する更新。使わない場合は()にする
+    type Update: Clone;
+
+    // valueにupdateを適用する
+    fn apply(_update: &Self::Update, _value: &mut V) {}
+
+    // size個のノードを持つ部分木のsummaryに、全てのvalueにupdateを適用した後のsummaryを返す
+    fn apply_summary(_update: &Self::Update, summary: &Self::Summary, _size: usize) -> Self::Summary {
+        summary.clone()
+    }
+
+    // firstを適用した後にsecondを適用するのと同じupdateを返す
+    fn compose(_first: &Self::Update, second: &Self::Update) -> Self::Update {
+        second.clone()
+    }
+
+    // apply_rangeを使う(apply/composeを実装する)場合はtrueにする
+    // falseの場合は溜まっているupdateを適用するために根から辿らなくてよい。apply_rangeを呼ぶとpanicする
+    const HAS_UPDATE: bool = false;
+}
+
+// 何も集約しない場合
+impl<K, V> Augment<K, V> for () {
+    type Summary = ();
+    type Update = ();
+
+    fn empty() {}
+
+    fn combine(_left: &(), _node: (&K, &V), _right: &()) {}
+
+    const HAS_UPDATE: bool = false;
+}
+
+// apply_rangeを使わないAugment。これを実装したAugmentの木はSyncになる
+// 実装する側が守ること(unsafe traitなのはこのため):
+// HAS_UPDATEをfalseにしなければならない。trueのままだと、&selfの読み取りの途中でも溜まっているupdateを子に適用するので、
+// 複数のスレッドから同時に読むとノードの書き換えが競合する
+#[allow(clippy::missing_safety_doc)]
+pub unsafe trait NoUpdate<K, V>: Augment<K, V> {}
+
+unsafe impl<K, V> NoUpdate<K, V> for () {}
+
+// keyの順序の決め方。木の中のkeyは全てこの順序で並ぶ
+// 木を分割する時に同じ順序を持った木を作るので、Cloneできる必要がある
+pub trait Compare<K: ?Sized>: Clone {
+    fn compare(&self, a: &K, b: &K) -> Ordering;
+}
+
+// KのOrdの順序で並べる場合
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Natural;
+
+impl<K: Ord + ?Sized> Compare<K> for Natural {
+    fn compare(&self, a: &K, b: &K) -> Ordering {
+        a.cmp(b)
+    }
+}
+
+// |a: &K, b: &K| a.cmp(b).reverse() のようなクロージャもそのまま使える
+impl<K: ?Sized, F: Fn(&K, &K) -> Ordering + Clone> Compare<K> for F {
+    fn compare(&self, a: &K, b: &K) -> Ordering {
+        self(a, b)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Color {
+    Black, Red,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Rotate {
+    Left, Right,
+}
+
+// slabの中でノードが置かれている位置
+// 0を使わずにindex + 1を持つことで、Option<NodeId>も4バイトに収める
+// 色と一緒に31ビットに詰めるので、NO_PARENTより小さい値しか使えない
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct NodeId(NonZeroU32);
+
+impl NodeId {
+    #[inline]
+    fn new(index: usize) -> NodeId {
+        let id = u32::try_from(index + 1).ok().filter(|id| *id < ParentColor::NO_PARENT).and_then(NonZeroU32::new);
+        NodeId(id.expect("rb-tree can not hold more than 2^31 - 2 nodes"))
+    }
+
+    #[inline]
+    fn index(self) -> usize {
+        self.0.get() as usize - 1
+    }
+}
+
+// 親のNodeIdと色を1つのu32にまとめたもの
+// Linuxカーネルのrbtreeが親へのポインタの下位ビットに色を持つのと同じように、最下位ビットを色(0: 黒, 1: 赤)にして、残りのビットに親のNodeIdを持つ
+// 親がいない場合は残りのビットを全て1にするので、0になることはなく、Slotの区別にも使える
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct ParentColor(NonZeroU32);
+
+impl ParentColor {
+    const NO_PARENT: u32 = u32::MAX >> 1;
+
+    #[inline]
+    fn new(parent: Option<NodeId>, color: Color) -> ParentColor {
+        let parent = parent.map_or(Self::NO_PARENT, |parent| parent.0.get());
+        let color = match color {
+            Color::Black => 0,
+            Color::Red => 1,
+        };
+        ParentColor(NonZeroU32::new(parent << 1 | color).unwrap())
+    }
+
+    #[inline]
+    fn parent(self) -> Option<NodeId> {
+        match self.0.get() >> 1 {
+            Self::NO_PARENT => None,
+            parent => NonZeroU32::new(parent).map(NodeId),
+        }
+    }
+
+    #[inline]
+    fn color(self) -> Color {
+        if self.0.get() & 1 == 0 { Color::Black } else { Color::Red }
+    }
+}
+
+impl Debug for ParentColor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "ParentColor {{ parent: {:?}, color: {:?} }}", self.parent(), self.color())
+    }
+}
+
+struct RBNode<K, V, A: Augment<K, V> = ()> {
+    key: K,
+    // 読み取りの途中でも溜まっているupdateを子に適用するので、valueとsummaryとtagはUnsafeCellに入れる
+    value: UnsafeCell<V>,
+    // 親と色
+    parent_color: ParentColor,
+    left: Option<NodeId>,
+    right: Option<NodeId>,
+    // 自分を根とする部分木のノード数。ノードの数はNodeIdと同じくu32に収まる
+    size: u32,
+    // 自分を根とする部分木のsummary
+    summary: UnsafeCell<A::Summary>,
+    // 左右の子の部分木にまだ適用していないupdate(自分のvalueとsummaryには適用済み)
+    tag: UnsafeCell<Option<A::Update>>,
+}
+
+impl<K, V, A: Augment<K, V>> RBNode<K, V, A> {
+    #[inline]
+    fn pair(self) -> (K, V) {
+        (self.key, self.value.into_inner())
+    }
+}
+
+// 親子はNodeIdのままコピーするので、同じslabの中でしか意味を持たない
+// 色、ノード数、summary、溜まっているupdateもそのままコピーする
+impl<K: Clone, V: Clone, A: Augment<K, V>> Clone for RBNode<K, V, A> {
+    fn clone(&self) -> RBNode<K, V, A> {
+        RBNode {
+            key: self.key.clone(),
+            value: UnsafeCell::new(self.value().clone()),
+            parent_color: self.parent_color,
+            left: self.left,
+            right: self.right,
+            size: self.size,
+            summary: UnsafeCell::new(self.summary().clone()),
+            tag: UnsafeCell::new(unsafe { (*self.tag.get()).clone() }),
+        }
+    }
+}
+
+impl<K: Debug, V, A: Augment<K, V>> Debug for RBNode<K, V, A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "RBNode [ key: {:?}, color: {:?}, parent: {:#?}, left: {:?}, right: {:?}, size: {:?} ]", self.key, self.get_color(), self.parent(), self.left, self.right, self.size)?;
+        Ok(())
+    }
+}
+
+impl<K, V, A: Augment<K, V>> RBNode<K, V, A> {
+    fn new(key: K, value: V) -> RBNode<K, V, A> {
+        let summary = A::combine(&A::empty(), (&key, &value), &A::empty());
+        RBNode {
+            key,
+            value: UnsafeCell::new(value),
+            parent_color: ParentColor::new(None, Color::Red),
+            left: None,
+            right: None,
+            size: 1,
+            summary: UnsafeCell::new(summary),
+            tag: UnsafeCell::new(None),
+        }
+    }
+
+    // 祖先に溜まっているupdateがまだ適用されていない場合がある
+    #[inline]
+    fn value(&self) -> &V {
+        unsafe { &*self.value.get() }
+    }
+
+    #[inline]
+    fn value_mut(&mut self) -> &mut V {
+        self.value.get_mut()
+    }
+
+    #[inline]
+    fn summary(&self) -> &A::Summary {
+        unsafe { &*self.summary.get() }
+    }
+
+    #[inline]
+    fn parent(&self) -> Option<NodeId> {
+        self.parent_color.parent()
+    }
+
+    #[inline]
+    fn set_parent(&mut self, parent: Option<NodeId>) {
+        self.parent_color = ParentColor::new(parent, self.get_color());
+    }
+
+    #[inline]
+    fn set_color(&mut self, color: Color) {
+        self.parent_color = ParentColor::new(self.parent(), color);
+    }
+
+
+
+    #[inline]
+    fn get_color(&self) -> Color {
+        self.parent_color.color()
+    }
+
+    #[inline]
+    fn is_red(&self) -> bool {
+        self.get_color() == Color::Red
+    }
+
+    #[inline]
+    fn is_black(&self) -> bool {
+        self.get_color() == Color::Black
+    }
+}
+
+// 木の中のノードへの参照。Derefでノードのフィールドを読み、子や次のノードを辿ることができる
+// 子に降りる時は溜まっているupdateを適用するので、辿った先のvalueには祖先のupdateが反映されている
+// テストで木の形を確かめるためだけに使う
+#[cfg(test)]
+struct NodeRef<'a, K, V, A: Augment<K, V> = (), C: Compare<K> = Natural> {
+    tree: &'a RBTree<K, V, A, C>,
+    id: NodeId,
+}
+
+#[cfg(test)]
+impl<'a, K, V, A: Augment<K, V>, C: Compare<K>> Clone for NodeRef<'a, K, V, A, C> {
+    fn clone(&self) -> NodeRef<'a, K, V, A, C> {
+        *self
+    }
+}
+
+#[cfg(test)]
+impl<'a, K, V, A: Augment<K, V>, C: Compare<K>> Copy for NodeRef<'a, K, V, A, C> {}
+
+#[cfg(test)]
+impl<'a, K, V, A: Augment<K, V>, C: Compare<K>> Deref for NodeRef<'a, K, V, A, C> {
+    type Target = RBNode<K, V, A>;
+
+    fn deref(&self) -> &RBNode<K, V, A> {
+        self.tree.node(self.id)
+    }
+}
+
+#[cfg(test)]
+impl<'a, K, V, A: Augment<K, V>, C: Compare<K>> NodeRef<'a, K, V, A, C> {
+    #[inline]
+    fn left(&self) -> Option<NodeRef<'a, K, V, A, C>> {
+        self.tree.push_down(self.id);
+        self.tree.node(self.id).left.map(|left| self.tree.node_ref(left))
+    }
+
+    #[inline]
+    fn right(&self) -> Option<NodeRef<'a, K, V, A, C>> {
+        self.tree.push_down(self.id);
+        self.tree.node(self.id).right.map(|right| self.tree.node_ref(right))
+    }
+
+    #[inline]
+    fn next(&self) -> Option<NodeRef<'a, K, V, A, C>> {
+        self.tree.successor(self.id).map(|next| self.tree.node_ref(next))
+    }
+
+    #[inline]
+    fn min_node(&self) -> NodeRef<'a, K, V, A, C> {
+        self.tree.node_ref(self.tree.find_minimum(self.id))
+    }
+}
+
+// slabの1つの枠。空いている枠は次の空いている枠を指して、空き枠のリストを作る
+enum Slot<K, V, A: Augment<K, V> = ()> {
+    Occupied(RBNode<K, V, A>),
+    Vacant(Option<NodeId>),
+}
+
+impl<K: Clone, V: Clone, A: Augment<K, V>> Clone for Slot<K, V, A> {
+    fn clone(&self) -> Slot<K, V, A> {
+        match self {
+            Slot::Occupied(node) => Slot::Occupied(node.clone()),
+            Slot::Vacant(next) => Slot::Vacant(*next),
+        }
+    }
+}
+
+// 先頭の要素だけで比較できるkey
+// (String, u64)のkeyを&strのprefixで比較できるように、先頭の要素はBorrowした型で比較する
+pub trait KeyPrefix<P: ?Sized> {
+    fn cmp_prefix(&self, prefix: &P) -> Ordering;
+}
+
+impl<P: Ord + ?Sized, T0: Borrow<P>, T1> KeyPrefix<P> for (T0, T1) {
+    fn cmp_prefix(&self, prefix: &P) -> Ordering {
+        self.0.borrow().cmp(prefix)
+    }
+}
+
+impl<P: Ord + ?Sized, T0: Borrow<P>, T1, T2> KeyPrefix<P> for (T0, T1, T2) {
+    fn cmp_prefix(&self, prefix: &P) -> Ordering {
+        self.0.borrow().cmp(prefix)
+    }
+}
+
+// 文字列やバイト列のように、先頭部分が一致するかを調べられるkey
+// OrdはRBTree::prefixで連続した範囲になるように、先頭から辞書順に比較する必要がある
+pub trait BytePrefix: Ord {
+    fn starts_with(&self, prefix: &Self) -> bool;
+
+    // otherと先頭から一致する部分
+    fn common_prefix<'a>(&'a self, other: &Self) -> &'a Self;
+}
+
+impl BytePrefix for str {
+    fn starts_with(&self, prefix: &str) -> bool {
+        str::starts_with(self, prefix)
+    }
+
+    fn common_prefix<'a>(&'a self, other: &str) -> &'a str {
+        let mut len = self.as_bytes().common_prefix(other.as_bytes()).len();
+        // 文字の途中で切らない
+        while !self.is_char_boundary(len) { len -= 1; }
+        &self[..len]
+    }
+}
+
+impl BytePrefix for [u8] {
+    fn starts_with(&self, prefix: &[u8]) -> bool {
+        <[u8]>::starts_with(self, prefix)
+    }
+
+    fn common_prefix<'a>(&'a self, other: &[u8]) -> &'a [u8] {
+        let len = self.iter().zip(other).take_while(|(a, b)| a == b).count();
+        &self[..len]
+    }
+}
+
+// keyが範囲の開始位置以降にあるか
+fn is_after_start<Q: ?Sized, C: Compare<Q>>(cmp: &C, key: &Q, start: Bound<&Q>) -> bool {
+    match start {
+        Bound::Included(start) => cmp.compare(key, start) != Ordering::Less,
+        Bound::Excluded(start) => cmp.compare(key, start) == Ordering::Greater,
+        Bound::Unbounded => true,
+    }
+}
+
+// keyが範囲の終了位置以前にあるか
+fn is_before_end<Q: ?Sized, C: Compare<Q>>(cmp: &C, key: &Q, end: Bound<&Q>) -> bool {
+    match end {
+        Bound::Included(end) => cmp.compare(key, end) != Ordering::Greater,
+        Bound::Excluded(end) => cmp.compare(key, end) == Ordering::Less,
+        Bound::Unbounded => true,
+    }
+}
+
+// split/joinで扱う切り離された部分木(根, 黒高さ)
+type SubTree = (Option<NodeId>, usize);
+
+pub struct RBTree<K, V, A: Augment<K, V> = (), C = Natural> {
+    // 全てのノードを置くslab。親子はslabの中の位置で指す
+    nodes: Vec<Slot<K, V, A>>,
+    // 空いている枠のリストの先頭。removeで空いた枠は次のinsertで使い回す
+    free: Option<NodeId>,
+    root: Option<NodeId>,
+    len: usize,
+    // keyの順序
+    cmp: C,
+    // 枠ごとの世代。枠のノードを取り除くたびに増やして、取り除いたノードを指すNodeHandleを見分ける
+    // NodeHandleを使わない木で枠ごとに4バイト増えないように、最初にNodeHandleを作るまでは空にしておく
+    generations: Vec<u32>,
+    // generationsを空にした後に使う世代。それまでに作ったどのNodeHandleの世代よりも大きい
+    generation_base: u32,
+}
+
+// insert_with_handleで挿入した要素を指す。回転などで木の形が変わっても同じ要素を指し続ける
+// 要素を取り除いた後は、同じ枠に別の要素が置かれても世代が違うので使えなくなる
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NodeHandle {
+    id: NodeId,
+    generation: u32,
+}
+
+// BTreeMapと同じく {k: v, ...} の形でkeyの順に表示する
+impl<K: Debug, V: Debug, A: Augment<K, V>, C: Compare<K>> fmt::Debug for RBTree<K, V, A, C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: Ord, V, A: Augment<K, V>> RBTree<K, V, A> {
+    pub fn new() -> RBTree<K, V, A> {
+        RBTree::with_comparator(Natural)
+    }
+
+    // capacity個のノードを置けるslabを最初に確保しておく
+    pub fn with_capacity(capacity: usize) -> RBTree<K, V, A> {
+        RBTree::with_capacity_and_comparator(capacity, Natural)
+    }
+
+    // keyの昇順に並んだ(重複のない)要素から、O(n)で木を組み立てる
+    // 中央の要素を根にして再帰的に組み立てると、葉までの深さの差が高々1になるので、
+    // 最も深い段のノードだけを赤にすれば赤黒木の性質を満たす
+    fn from_sorted_iter<I: IntoIterator<Item = (K, V)>>(entries: I) -> RBTree<K, V, A> {
+        let mut tree = RBTree::new();
+        tree.fill_sorted(entries.into_iter().collect());
+        tree
+    }
+
+    // keyの先頭の要素がprefixと等しい要素を昇順に返す
+    // (tenant, timestamp)のkeyを持つ木から、あるtenantの要素だけを取り出すような場合に使う
+    // tupleはOrdで先頭の要素から順に比較するので、同じprefixを持つkeyは木の中で連続している
+    pub fn prefix_range<P: ?Sized>(&self, prefix: &P) -> Iter<'_, K, V, A>
+        where K: KeyPrefix<P> {
+        let front = self.lower_bound_by(|key| key.cmp_prefix(prefix) != Ordering::Less);
+        let back = self.upper_bound_by(|key| key.cmp_prefix(prefix) != Ordering::Greater);
+        self.iter_between(front, back)
+    }
+
+    // prefixで始まるkeyを持つ要素を昇順に返す。入力補完のように、先頭の一致するkeyを全て取り出す場合に使う
+    // prefixで始まるkeyはprefix以上で、木の中で連続しているので、両端をO(log n)で探せる
+    pub fn prefix<P: BytePrefix + ?Sized>(&self, prefix: &P) -> Iter<'_, K, V, A>
+        where K: Borrow<P> {
+        let front = self.lower_bound_by(|key| key.borrow() >= prefix);
+        let back = self.upper_bound_by(|key| key.borrow() < prefix || key.borrow().starts_with(prefix));
+        self.iter_between(front, back)
+    }
+
+    // keyの先頭部分になっているkeyのうち、最も長いものを持つ要素を返す。経路表の検索のような場合に使う
+    pub fn longest_prefix_match<P: BytePrefix + ?Sized>(&self, key: &P) -> Option<(&K, &V)>
+        where K: Borrow<P> {
+        let mut key = key;
+        loop {
+            // key以下の最大のkey。これがkeyの先頭部分でなければ、答えは両者の共通部分の先頭部分になっている
+            // (keyの先頭部分pとkeyの間にあるkeyは全てpで始まるので)
+            let node = self.node(self.upper_bound_by(|candidate| candidate.borrow() <= key)?);
+            let candidate = node.key.borrow();
+            if key.starts_with(candidate) {
+                return Some((&node.key, node.value()));
+            }
+            key = key.common_prefix(candidate);
+        }
+    }
+}
+
+impl<K, V, A: Augment<K, V>, C: Compare<K>> RBTree<K, V, A, C> {
+    // KのOrdではなくcmpの順序でkeyを並べる
+    pub fn with_comparator(cmp: C) -> RBTree<K, V, A, C> {
+        RBTree::with_capacity_and_comparator(0, cmp)
+    }
+
+    pub fn with_capacity_and_comparator(capacity: usize, cmp: C) -> RBTree<K, V, A, C> {
+        RBTree {
+            nodes: Vec::with_capacity(capacity),
+            free: None,
+            root: None,
+            len: 0,
+            cmp,
+            generations: Vec::new(),
+            generation_base: 0,
+        }
+    }
+
+    // 同じ順序を持つ空の木
+    fn empty_like(&self, capacity: usize) -> RBTree<K, V, A, C> {
+        RBTree::with_capacity_and_comparator(capacity, self.cmp.clone())
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // 全ての要素を取り除く。確保したslabは残しておく
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.reset_generations();
+        self.free = None;
+        self.root = None;
+        self.len = 0;
+    }
+
+    // slabを伸ばさずに置けるノードの数
+    pub fn capacity(&self) -> usize {
+        self.nodes.capacity()
+    }
+
+    // 空いている枠を詰めてslabを縮める。ノードを根から順に置き直すので、ノードの位置は変わる
+    // 位置が変わった場合は、それまでに作ったNodeHandleは全て使えなくなる
+    pub fn shrink_to_fit(&mut self) {
+        if self.free.is_some() {
+            let mut compacted = self.empty_like(self.len);
+            let root = self.root.take();
+            compacted.root = self.move_subtree(root, None, &mut compacted);
+            self.nodes = compacted.nodes;
+            self.reset_generations();
+            self.free = None;
+            self.root = compacted.root;
+        }
+        self.nodes.shrink_to_fit();
+    }
+
+    #[inline]
+    fn node(&self, id: NodeId) -> &RBNode<K, V, A> {
+        match &self.nodes[id.index()] {
+            Slot::Occupied(node) => node,
+            Slot::Vacant(_) => panic!("rb-tree node({}) is already removed.", id.index()),
+        }
+    }
+
+    #[inline]
+    fn node_mut(&mut self, id: NodeId) -> &mut RBNode<K, V, A> {
+        match &mut self.nodes[id.index()] {
+            Slot::Occupied(node) => node,
+            Slot::Vacant(_) => panic!("rb-tree node({}) is already removed.", id.index()),
+        }
+    }
+
+    #[cfg(test)]
+    #[inline]
+    fn node_ref(&self, id: NodeId) -> NodeRef<'_, K, V, A, C> {
+        NodeRef { tree: self, id }
+    }
+
+    #[cfg(test)]
+    #[inline]
+    fn root_node(&self) -> Option<NodeRef<'_, K, V, A, C>> {
+        self.root.map(|root| self.node_ref(root))
+    }
+
+    // ノードを空いている枠に置く。空いている枠がなければslabを伸ばす
+    fn allocate(&mut self, node: RBNode<K, V, A>) -> NodeId {
+        match self.free {
+            Some(id) => {
+                match mem::replace(&mut self.nodes[id.index()], Slot::Occupied(node)) {
+                    Slot::Vacant(next) => self.free = next,
+                    Slot::Occupied(_) => unreachable!("free list points to an occupied slot"),
+                }
+                id
+            },
+            None => {
+                let id = NodeId::new(self.nodes.len());
+                self.nodes.push(Slot::Occupied(node));
+                if !self.generations.is_empty() { self.generations.push(self.generation_base); }
+                id
+            },
+        }
+    }
+
+    // ノードを枠から取り出して、枠を空いている枠のリストに繋ぐ
+    fn deallocate(&mut self, id: NodeId) -> RBNode<K, V, A> {
+        match mem::replace(&mut self.nodes[id.index()], Slot::Vacant(self.free)) {
+            Slot::Occupied(node) => {
+                self.free = Some(id);
+                // 2^32回使い回した枠では古いNodeHandleと世代が一致してしまうが、そこまでは気にしない
+                if let Some(generation) = self.generations.get_mut(id.index()) {
+                    *generation = generation.wrapping_add(1);
+                }
+                node
+            },
+            Slot::Vacant(_) => panic!("rb-tree node({}) is already removed.", id.index()),
+        }
+    }
+
+    // 全ての枠の位置が変わった(または枠がなくなった)ので、それまでに作ったNodeHandleを全て使えなくする
+    fn reset_generations(&mut self) {
+        if let Some(max) = self.generations.iter().max() {
+            self.generation_base = self.generation_base.max(max.wrapping_add(1));
+        }
+        self.generations.clear();
+    }
+
+    fn handle(&mut self, id: NodeId) -> NodeHandle {
+        if self.generations.is_empty() {
+            self.generations = vec![self.generation_base; self.nodes.len()];
+        }
+        NodeHandle { id, generation: self.generations[id.index()] }
+    }
+
+    // handleがまだ木の中の要素を指している場合は、そのノードを返す
+    fn handle_node(&self, handle: NodeHandle) -> Option<NodeId> {
+        let generation = *self.generations.get(handle.id.index())?;
+        if generation != handle.generation { return None; }
+        match self.nodes[handle.id.index()] {
+            Slot::Occupied(_) => Some(handle.id),
+            Slot::Vacant(_) => None,
+        }
+    }
+
+    // insertと同じく挿入して、挿入した要素を指すNodeHandleを返す
+    // 同じkeyがすでに存在する場合はvalueを上書きして、その要素を指すNodeHandleを返す
+    pub fn insert_with_handle(&mut self, key: K, value: V) -> (NodeHandle, Option<V>) {
+        let (node, old_value) = self.insert_node(key, value);
+        (self.handle(node), old_value)
+    }
+
+    // keyを持つ要素を指すNodeHandleを返す
+    pub fn handle_of<Q>(&mut self, key: &Q) -> Option<NodeHandle>
+        where K: Borrow<Q>, Q: ?Sized, C: Compare<Q> {
+        let node = self.search(key)?;
+        Some(self.handle(node))
+    }
+
+    // keyで探さずに要素を返す。要素がすでに取り除かれている場合はNone
+    // updateを使わない木ではO(1)で、使う木では祖先に溜まっているupdateを適用するのでO(log n)になる
+    pub fn get_by_handle(&self, handle: NodeHandle) -> Option<(&K, &V)> {
+        let node = self.handle_node(handle)?;
+        if A::HAS_UPDATE { self.push_path(node); }
+        let node = self.node(node);
+        Some((&node.key, node.value()))
+    }
+
+    // keyで探さずに要素を取り除く。木の組み替えとsummaryの計算し直しにはO(log n)かかる
+    pub fn remove_by_handle(&mut self, handle: NodeHandle) -> Option<(K, V)> {
+        let node = self.handle_node(handle)?;
+        Some(self.remove_node(node))
+    }
+
+    // keyの昇順で次の要素を指すNodeHandleを返す。順に辿る場合はならしてO(1)になる
+    pub fn next_handle(&mut self, handle: NodeHandle) -> Option<NodeHandle> {
+        let next = self.successor(self.handle_node(handle)?)?;
+        Some(self.handle(next))
+    }
+
+    pub fn prev_handle(&mut self, handle: NodeHandle) -> Option<NodeHandle> {
+        let prev = self.predecessor(self.handle_node(handle)?)?;
+        Some(self.handle(prev))
+    }
+
+    pub(crate) fn find_minimum(&self, partial: NodeId) -> NodeId {
+        self.push_down(partial);
+        match self.left(partial) {
+            Some(left) => self.find_minimum(left),
+            None => partial,
+        }
+    }
+
+    pub(crate) fn find_maximum(&self, partial: NodeId) -> NodeId {
+        self.push_down(partial);
+        match self.right(partial) {
+            Some(right) => self.find_maximum(right),
+            None => partial,
+        }
+    }
+
+    // BTreeMapと同じく、同じkeyがすでに存在する場合はvalueを上書きして、古いvalueを返す
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.insert_node(key, value).1
+    }
+
+    // 挿入(または上書き)したノードと、上書きした場合は古いvalueを返す
+    fn insert_node(&mut self, key: K, value: V) -> (NodeId, Option<V>) {
+        let mut current_node = match self.root {
+            Some(root) => root,
+            None => {
+                let root = self.allocate(RBNode::new(key, value));
+                self.set_color(root, Color::Black);
+                self.root = Some(root);
+                self.len = 1;
+                return (root, None);
+            },
+        };
+        loop {
+            self.push_down(current_node);
+            let ordering = self.cmp.compare(&key, &self.node(current_node).key);
+            if ordering == Ordering::Equal {
+                let old_value = mem::replace(self.node_mut(current_node).value_mut(), value);
+                // valueが変わったのでsummaryを計算し直す
+                self.update_nodes_to_root(Some(current_node));
+                return (current_node, Some(old_value));
+            }
+            if ordering == Ordering::Less { // keyが現在のnodeのkeyよりも小さい場合
+                if let Some(left) = self.left(current_node) { // 現在のnodeに左側の子が存在した場合は次にそいつと比較する
+                    current_node = left;
+                    continue;
+                } else { // 現在のnodeに左側に子が存在しなかった場合はinsertする
+                    // insert
+                    let node = self.allocate(RBNode::new(key, value));
+                    self.node_mut(current_node).left = Some(node);
+                    self.node_mut(node).set_parent(Some(current_node));
+                    self.update_nodes_to_root(Some(current_node));
+                    self.change_structure(node);
+                    self.len += 1;
+                    return (node, None);
+                }
+            }
+            if ordering == Ordering::Greater { // keyが現在のnodeのkeyよりも大きい場合、次にそいつと比較する
+                if let Some(right) = self.right(current_node) {
+                    current_node = right;
+                    continue;
+                } else { // 現在のnodeに右側に子が存在しなかった場合はinsertする
+                    // insert
+                    let node = self.allocate(RBNode::new(key, value));
+                    self.node_mut(current_node).right = Some(node);
+                    self.node_mut(node).set_parent(Some(current_node));
+                    self.update_nodes_to_root(Some(current_node));
+                    self.change_structure(node);
+                    self.len += 1;
+                    return (node, None);
+                }
+            }
+        }
+    }
+
+    // 同じkeyがすでに存在していても上書きせず、同じkeyを持つノードの後ろ(右側)に追加する
+    // 同じkeyのノードは挿入した順に並ぶ
+    fn insert_multi(&mut self, key: K, value: V) -> NodeId {
+        let node = self.allocate(RBNode::new(key, value));
+        self.len += 1;
+        let mut current_node = match self.root {
+            Some(root) => root,
+            None => {
+                self.set_color(node, Color::Black);
+                self.root = Some(node);
+                return node;
+            },
+        };
+        loop {
+            self.push_down(current_node);
+            if self.cmp.compare(&self.node(node).key, &self.node(current_node).key) == Ordering::Less {
+                match self.left(current_node) {
+                    Some(left) => current_node = left,
+                    None => {
+                        self.node_mut(current_node).left = Some(node);
+                        break;
+                    },
+                }
+            } else {
+                match self.right(current_node) {
+                    Some(right) => current_node = right,
+                    None => {
+                        self.node_mut(current_node).right = Some(node);
+                        break;
+                    },
+                }
+            }
+        }
+        self.node_mut(node).set_parent(Some(current_node));
+        self.update_nodes_to_root(Some(current_node));
+        self.change_structure(node);
+        node
+    }
+
+    // 取り除いた要素のvalueを返す。keyが存在しない場合はNone
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+        where K: Borrow<Q>, Q: ?Sized, C: Compare<Q> {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+        where K: Borrow<Q>, Q: ?Sized, C: Compare<Q> {
+        let remove_node = self.search(key)?;
+        Some(self.remove_node(remove_node))
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+        where K: Borrow<Q>, Q: ?Sized, C: Compare<Q> {
+        Some(self.node(self.search(key)?).value())
+    }
+
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+        where K: Borrow<Q>, Q: ?Sized, C: Compare<Q> {
+        let node = self.node(self.search(key)?);
+        Some((&node.key, node.value()))
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+        where K: Borrow<Q>, Q: ?Sized, C: Compare<Q> {
+        self.search(key).is_some()
+    }
+
+    pub fn first_key_value(&self) -> Option<(&K, &V)> {
+        let minimum_node = self.node(self.find_minimum(self.root?));
+        Some((&minimum_node.key, minimum_node.value()))
+    }
+
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        let maximum_node = self.node(self.find_maximum(self.root?));
+        Some((&maximum_node.key, maximum_node.value()))
+    }
+
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        let minimum_node = self.find_minimum(self.root?);
+        Some(self.remove_node(minimum_node))
+    }
+
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        let maximum_node = self.find_maximum(self.root?);
+        Some(self.remove_node(maximum_node))
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V, A, C> {
+        Iter {
+            tree: self,
+            front: self.root.map(|root| self.find_minimum(root)),
+            back: self.root.map(|root| self.find_maximum(root)),
+            len: self.len,
+        }
+    }
+
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &K> + ExactSizeIterator + '_ {
+        self.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &V> + ExactSizeIterator + '_ {
+        self.iter().map(|(_, value)| value)
+    }
+
+    pub fn into_keys(self) -> impl DoubleEndedIterator<Item = K> + ExactSizeIterator {
+        self.into_iter().map(|(key, _)| key)
+    }
+
+    pub fn into_values(self) -> impl DoubleEndedIterator<Item = V> + ExactSizeIterator {
+        self.into_iter().map(|(_, value)| value)
+    }
+
+    // fがfalseを返した要素を取り除く。残った要素はノードを動かさないので、NodeHandleもそのまま使える
+    // valueが変わるかもしれないので、最後に全てのノードのsummaryを計算し直す
+    pub fn retain<F: FnMut(&K, &mut V) -> bool>(&mut self, mut f: F) {
+        let mut node = self.root.map(|root| self.find_minimum(root));
+        while let Some(current_node) = node {
+            // 取り除く前に次のノードを求めておく。他のノードは取り除いても枠が変わらない
+            node = self.successor(current_node);
+            let entry = self.node_mut(current_node);
+            if !f(&entry.key, entry.value.get_mut()) {
+                self.remove_node(current_node);
+            }
+        }
+        self.update_subtree(self.root);
+    }
+
+    // otherの要素を全て移す。同じkeyが存在する場合はotherのvalueで上書きする
+    // この木のノードは動かさずに1つずつ挿入するので、この木の要素を指すNodeHandleはそのまま使える
+    // この木が空の場合は、otherの要素を昇順に取り出して並べ直すのでO(m)で済む
+    pub fn append(&mut self, other: &mut RBTree<K, V, A, C>) {
+        if other.is_empty() { return; }
+        let entries = other.take_sorted();
+        if self.is_empty() {
+            self.fill_sorted(entries);
+            return;
+        }
+        for (key, value) in entries {
+            self.insert(key, value);
+        }
+    }
+
+    // key以降のkeyを持つ要素を切り離して返す
+    pub fn split_off<Q>(&mut self, key: &Q) -> RBTree<K, V, A, C>
+        where K: Borrow<Q>, Q: ?Sized, C: Compare<Q> {
+        self.remove_range((Bound::Included(key), Bound::Unbounded))
+    }
+
+    pub fn range<Q, R>(&self, range: R) -> Iter<'_, K, V, A, C>
+        where K: Borrow<Q>, Q: ?Sized, C: Compare<Q>, R: RangeBounds<Q> {
+        let front = self.lower_bound(range.start_bound());
+        let back = self.upper_bound(range.end_bound());
+        self.iter_between(front, back)
+    }
+
+    // frontからbackまでを返す
+    fn iter_between(&self, front: Option<NodeId>, back: Option<NodeId>) -> Iter<'_, K, V, A, C> {
+        // 開始位置が終了位置より後ろにある場合は空にする
+        let is_empty = match (front, back) {
+            (Some(front), Some(back)) => self.cmp.compare(&self.node(front).key, &self.node(back).key) == Ordering::Greater,
+            _ => true,
+        };
+        if is_empty {
+            return Iter { tree: self, front: None, back: None, len: 0 };
+        }
+        // 両端の位置から間にある要素の数をO(log n)で数える
+        let len = self.position(back.unwrap()) - self.position(front.unwrap()) + 1;
+        Iter { tree: self, front, back, len }
+    }
+
+    // 空の木に、この木の順序で昇順に並んだ(重複のない)要素を並べる。組み立て方はfrom_sorted_iterと同じ
+    fn fill_sorted(&mut self, entries: Vec<(K, V)>) {
+        let len = entries.len();
+        // 全てのノードが埋まっている段の数。これより深い段のノードが赤になる
+        let mut full_depth = 0;
+        while (1 << (full_depth + 1)) - 1 <= len { full_depth += 1; }
+        self.nodes.reserve(len);
+        self.root = self.build_sorted(&mut entries.into_iter(), len, 0, full_depth);
+        self.len = len;
+    }
+
+    // 全ての要素を昇順に取り出して、木を空にする。ノードを辿る順に取り出すのでO(n)で済む
+    fn take_sorted(&mut self) -> Vec<(K, V)> {
+        let mut order = Vec::with_capacity(self.len);
+        let mut node = self.root.map(|root| self.find_minimum(root));
+        while let Some(current_node) = node {
+            order.push(current_node);
+            node = self.successor(current_node);
+        }
+        let mut nodes = mem::take(&mut self.nodes);
+        self.reset_generations();
+        self.free = None;
+        self.root = None;
+        self.len = 0;
+        order.into_iter().map(|id| match mem::replace(&mut nodes[id.index()], Slot::Vacant(None)) {
+            Slot::Occupied(node) => node.pair(),
+            Slot::Vacant(_) => unreachable!("rb-tree node({}) is already removed.", id.index()),
+        }).collect()
+    }
+
+    fn build_sorted<I: Iterator<Item = (K, V)>>(&mut self, entries: &mut I, len: usize, depth: usize, full_depth: usize) -> Option<NodeId> {
+        if len == 0 { return None; }
+        let left_len = len / 2;
+        let left = self.build_sorted(entries, left_len, depth + 1, full_depth);
+        let (key, value) = entries.next().unwrap();
+        let node = self.allocate(RBNode::new(key, value));
+        let right = self.build_sorted(entries, len - left_len - 1, depth + 1, full_depth);
+        let color = if depth < full_depth { Color::Black } else { Color::Red };
+        self.set_color(node, color);
+        self.node_mut(node).left = left;
+        self.node_mut(node).right = right;
+        if let Some(left) = left { self.node_mut(left).set_parent(Some(node)); }
+        if let Some(right) = right { self.node_mut(right).set_parent(Some(node)); }
+        self.update_node(node);
+        Some(node)
+    }
+
+    // 昇順でindex番目(0始まり)の要素を返す
+    pub fn get_by_index(&self, index: usize) -> Option<(&K, &V)> {
+        let node = self.node(self.select(index)?);
+        Some((&node.key, node.value()))
+    }
+
+    // 昇順でindex番目(0始まり)の要素を取り除いて返す
+    pub fn remove_by_index(&mut self, index: usize) -> Option<(K, V)> {
+        let node = self.select(index)?;
+        Some(self.remove_node(node))
+    }
+
+    // keyより小さいkeyを持つ要素の数を返す
+    pub fn rank<Q>(&self, key: &Q) -> usize
+        where K: Borrow<Q>, Q: ?Sized, C: Compare<Q> {
+        self.count_less(key, false)
+    }
+
+    // 範囲内のkeyを持つ要素の数を返す
+    pub fn range_count<Q, R>(&self, range: R) -> usize
+        where K: Borrow<Q>, Q: ?Sized, C: Compare<Q>, R: RangeBounds<Q> {
+        let end_count = match range.end_bound() {
+            Bound::Included(end) => self.count_less(end, true),
+            Bound::Excluded(end) => self.count_less(end, false),
+            Bound::Unbounded => self.len,
+        };
+        let start_count = match range.start_bound() {
+            Bound::Included(start) => self.count_less(start, false),
+            Bound::Excluded(start) => self.count_less(start, true),
+            Bound::Unbounded => 0,
+        };
+        end_count.saturating_sub(start_count)
+    }
+
+    // 範囲内のkeyを持つ要素をkeyの昇順にcombineしたsummaryを返す
+    // 範囲に丸ごと含まれる部分木はsummaryをそのまま使うので、O(log n)回のcombineで済む
+    pub fn fold_range<Q, R>(&self, range: R) -> A::Summary
+        where K: Borrow<Q>, Q: ?Sized, C: Compare<Q>, R: RangeBounds<Q> {
+        self.fold_node(self.root, range.start_bound(), range.end_bound())
+    }
+
+    // 部分木のうち範囲内のkeyを持つ要素のsummaryを返す
+    // 範囲内のノードを見つけた後は、左の部分木は開始位置だけ、右の部分木は終了位置だけを気にすればよいので、
+    // 左右それぞれ1本のパスを降りるだけになる
+    fn fold_node<Q>(&self, node: Option<NodeId>, start: Bound<&Q>, end: Bound<&Q>) -> A::Summary
+        where K: Borrow<Q>, Q: ?Sized, C: Compare<Q> {
+        let node = match node {
+            Some(node) => node,
+            None => return A::empty(),
+        };
+        // 部分木が丸ごと範囲に含まれる場合
+        if let (Bound::Unbounded, Bound::Unbounded) = (start, end) {
+            return self.summary(Some(node));
+        }
+        // 子の部分木のsummaryを使うので、溜まっているupdateを先に適用しておく
+        self.push_down(node);
+        let current_node = self.node(node);
+        let key = current_node.key.borrow();
+        let after_start = is_after_start(&self.cmp, key, start);
+        let before_end = is_before_end(&self.cmp, key, end);
+        // 自分が範囲より前にある場合は右の部分木だけ、後ろにある場合は左の部分木だけを見る
+        if !after_start { return self.fold_node(current_node.right, start, end); }
+        if !before_end { return self.fold_node(current_node.left, start, end); }
+        A::combine(
+            &self.fold_node(current_node.left, start, Bound::Unbounded),
+            (&current_node.key, current_node.value()),
+            &self.fold_node(current_node.right, Bound::Unbounded, end),
+        )
+    }
+
+    // 範囲内のkeyを持つ全ての要素のvalueにupdateを適用する
+    // 範囲に丸ごと含まれる部分木は根にupdateを溜めておき、子に降りる時に適用するので、O(log n)で済む
+    // HAS_UPDATEがfalseの木ではupdateを溜めても子に適用しないので、黙って無視せずにpanicする
+    pub fn apply_range<Q, R>(&mut self, range: R, update: A::Update)
+        where K: Borrow<Q>, Q: ?Sized, C: Compare<Q>, R: RangeBounds<Q> {
+        assert!(A::HAS_UPDATE, "apply_range needs an Augment with HAS_UPDATE = true");
+        self.apply_node(self.root, range.start_bound(), range.end_bound(), &update);
+    }
+
+    // 部分木のうち範囲内のkeyを持つ要素にupdateを適用する。降り方はfold_nodeと同じ
+    fn apply_node<Q>(&mut self, node: Option<NodeId>, start: Bound<&Q>, end: Bound<&Q>, update: &A::Update)
+        where K: Borrow<Q>, Q: ?Sized, C: Compare<Q> {
+        let node = match node {
+            Some(node) => node,
+            None => return,
+        };
+        if let (Bound::Unbounded, Bound::Unbounded) = (start, end) {
+            self.apply_update(node, update);
+            return;
+        }
+        self.push_down(node);
+        let (left, right) = (self.left(node), self.right(node));
+        let key = self.node(node).key.borrow();
+        let after_start = is_after_start(&self.cmp, key, start);
+        let before_end = is_before_end(&self.cmp, key, end);
+        if !after_start {
+            self.apply_node(right, start, end, update);
+        } else if !before_end {
+            self.apply_node(left, start, end, update);
+        } else {
+            A::apply(update, self.node_mut(node).value_mut());
+            self.apply_node(left, start, Bound::Unbounded, update);
+            self.apply_node(right, Bound::Unbounded, end, update);
+        }
+        // 子の部分木のsummaryが変わったので計算し直す
+        self.update_node(node);
+    }
+
+    // 部分木のノード数を使って、昇順でindex番目のノードを探す
+    fn select(&self, index: usize) -> Option<NodeId> {
+        if index >= self.len { return None; }
+        let mut node = self.root?;
+        let mut index = index;
+        loop {
+            self.push_down(node);
+            let left_size = self.size(self.left(node));
+            match index.cmp(&left_size) {
+                Ordering::Equal => return Some(node),
+                Ordering::Less => node = self.left(node)?,
+                Ordering::Greater => {
+                    index -= left_size + 1;
+                    node = self.right(node)?;
+                },
+            }
+        }
+    }
+
+    // keyより小さい(inclusiveの場合はkey以下の)keyを持つ要素の数を返す
+    fn count_less<Q>(&self, key: &Q, inclusive: bool) -> usize
+        where K: Borrow<Q>, Q: ?Sized, C: Compare<Q> {
+        let mut count = 0;
+        let mut node = self.root;
+        while let Some(current_node) = node {
+            let current_node = self.node(current_node);
+            let is_less = match self.cmp.compare(current_node.key.borrow(), key) {
+                Ordering::Less => true,
+                Ordering::Equal => inclusive,
+                Ordering::Greater => false,
+            };
+            // 条件を満たす場合は、自分と左の部分木を数えて右に進む
+            if is_less {
+                count += self.size(current_node.left) + 1;
+                node = current_node.right;
+            } else {
+                node = current_node.left;
+            }
+        }
+        count
+    }
+
+    // 昇順でnodeが何番目(0始まり)かを返す。根まで登りながら、nodeより前にある部分木のノードを数える
+    fn position(&self, node: NodeId) -> usize {
+        let mut count = self.size(self.left(node));
+        let mut node = node;
+        while let Some(parent) = self.parent(node) {
+            if self.right(parent) == Some(node) { count += self.size(self.left(parent)) + 1; }
+            node = parent;
+        }
+        count
+    }
+
+    // 木からノードを切り離して、keyとvalueを返す。空いた枠は次のinsertで使い回す
+    fn remove_node(&mut self, remove_node: NodeId) -> (K, V) {
+        self.delete_node(remove_node);
+        self.len -= 1;
+        self.deallocate(remove_node).pair()
+    }
+
+    // Borrowしたkeyでノードを探す
+    fn search<Q>(&self, key: &Q) -> Option<NodeId>
+        where K: Borrow<Q>, Q: ?Sized, C: Compare<Q> {
+        let mut node = self.root?;
+        loop {
+            self.push_down(node);
+            match self.cmp.compare(key, self.node(node).key.borrow()) {
+                Ordering::Equal => return Some(node),
+                Ordering::Less => node = self.left(node)?,
+                Ordering::Greater => node = self.right(node)?,
+            }
+        }
+    }
+
+    // 開始位置の条件を満たす最小のノードを返す
+    fn lower_bound<Q>(&self, bound: Bound<&Q>) -> Option<NodeId>
+        where K: Borrow<Q>, Q: ?Sized, C: Compare<Q> {
+        self.lower_bound_by(|key| is_after_start(&self.cmp, key.borrow(), bound))
+    }
+
+    // satisfiedを満たす最小のノードを返す
+    // satisfiedはkeyの順序に対して単調(falseの後にtrueが続く)である必要がある
+    fn lower_bound_by<F: Fn(&K) -> bool>(&self, satisfied: F) -> Option<NodeId> {
+        let mut node = self.root;
+        let mut candidate = None;
+        while let Some(current_node) = node {
+            self.push_down(current_node);
+            let satisfied = satisfied(&self.node(current_node).key);
+            // 条件を満たす場合は候補にして、もっと小さいノードを左に探しに行く
+            if satisfied {
+                candidate = Some(current_node);
+                node = self.left(current_node);
+            } else {
+                node = self.right(current_node);
+            }
+        }
+        candidate
+    }
+
+    // 終了位置の条件を満たす最大のノードを返す
+    fn upper_bound<Q>(&self, bound: Bound<&Q>) -> Option<NodeId>
+        where K: Borrow<Q>, Q: ?Sized, C: Compare<Q> {
+        self.upper_bound_by(|key| is_before_end(&self.cmp, key.borrow(), bound))
+    }
+
+    // satisfiedを満たす最大のノードを返す
+    // satisfiedはkeyの順序に対して単調(trueの後にfalseが続く)である必要がある
+    fn upper_bound_by<F: Fn(&K) -> bool>(&self, satisfied: F) -> Option<NodeId> {
+        let mut node = self.root;
+        let mut candidate = None;
+        while let Some(current_node) = node {
+            self.push_down(current_node);
+            let satisfied = satisfied(&self.node(current_node).key);
+            // 条件を満たす場合は候補にして、もっと大きいノードを右に探しに行く
+            if satisfied {
+                candidate = Some(current_node);
+                node = self.right(current_node);
+            } else {
+                node = self.left(current_node);
+            }
+        }
+        candidate
+    }
+
+    // 範囲内のkeyを持つノードを切り離して、新しい木として返す
+    // 1ノードずつremoveせずにsplit/joinで切り離すので、木の組み替えはO(log n)で済む
+    // 切り離したノードは新しい木のslabに移すので、全体ではO(log n + 削除数)になる
+    pub fn remove_range<Q, R>(&mut self, range: R) -> RBTree<K, V, A, C>
+        where K: Borrow<Q>, Q: ?Sized, C: Compare<Q>, R: RangeBounds<Q> {
+        let root = self.root.take();
+        let height = self.black_height(root);
+        // splitの間は木を変更するので、順序は別に持っておく
+        let cmp = self.cmp.clone();
+        // 範囲の開始より前のkeyを持つ木と、それ以降のkeyを持つ木に分割する
+        let (left, rest) = self.split(root, height, &|key: &K| !is_after_start(&cmp, key.borrow(), range.start_bound()));
+        // 範囲の終わりまでのkeyを持つ木と、それより後のkeyを持つ木に分割する
+        let (middle, right) = self.split(rest.0, rest.1, &|key: &K| is_before_end(&cmp, key.borrow(), range.end_bound()));
+        self.root = self.join_without_node(left, right).0;
+        let (middle, _) = self.blacken(middle);
+        let removed_len = self.size(middle);
+        self.len -= removed_len;
+        let mut removed = self.empty_like(removed_len);
+        removed.root = self.move_subtree(middle, None, &mut removed);
+        removed.len = removed_len;
+        removed
+    }
+
+    // 部分木のノードを全てintoのslabに移して、parentの下に付ける
+    // 色、ノード数、summary、溜まっているupdateはそのまま持っていく
+    fn move_subtree(&mut self, node: Option<NodeId>, parent: Option<NodeId>, into: &mut RBTree<K, V, A, C>) -> Option<NodeId> {
+        let node = node?;
+        let mut moved_node = self.deallocate(node);
+        let (left, right) = (moved_node.left.take(), moved_node.right.take());
+        moved_node.set_parent(parent);
+        let moved = into.allocate(moved_node);
+        let left = self.move_subtree(left, Some(moved), into);
+        let right = self.move_subtree(right, Some(moved), into);
+        into.node_mut(moved).left = left;
+        into.node_mut(moved).right = right;
+        Some(moved)
+    }
+
+    // 部分木の全てのノードのノード数とsummaryを、葉の側から計算し直す
+    fn update_subtree(&mut self, node: Option<NodeId>) {
+        let node = match node {
+            Some(node) => node,
+            None => return,
+        };
+        self.push_down(node);
+        self.update_subtree(self.left(node));
+        self.update_subtree(self.right(node));
+        self.update_node(node);
+    }
+
+    // 部分木の黒高さ(部分木の根から葉までのパスにおける黒のノードの数)を返す
+    fn black_height(&self, node: Option<NodeId>) -> usize {
+        let mut height = 0;
+        let mut current_node = node;
+        while let Some(node) = current_node {
+            if self.node(node).is_black() { height += 1; }
+            current_node = self.left(node);
+        }
+        height
+    }
+
+    // 部分木のノード数を返す
+    fn size(&self, node: Option<NodeId>) -> usize {
+        match node {
+            Some(node) => self.node(node).size as usize,
+            None => 0,
+        }
+    }
+
+    // 部分木のsummaryを返す
+    fn summary(&self, node: Option<NodeId>) -> A::Summary {
+        match node {
+            Some(node) => self.node(node).summary().clone(),
+            None => A::empty(),
+        }
+    }
+
+    // 部分木全体にupdateを適用する。根のvalueとsummaryにはすぐに適用し、子の部分木の分は根に溜めておく
+    fn apply_update(&self, node: NodeId, update: &A::Update) {
+        let node = self.node(node);
+        // 読み取りの途中でも呼ばれるので、UnsafeCellを通して書き換える
+        // 溜まっているupdateを適用する前のvalueやsummaryへの参照は外に渡していないので、同時に参照されることはない
+        unsafe {
+            A::apply(update, &mut *node.value.get());
+            let summary = &mut *node.summary.get();
+            *summary = A::apply_summary(update, summary, node.size as usize);
+            let tag = &mut *node.tag.get();
+            *tag = Some(match tag.take() {
+                Some(tag) => A::compose(&tag, update),
+                None => update.clone(),
+            });
+        }
+    }
+
+    // 部分木の根が赤の場合は黒にする(黒高さが1増える)
+    fn blacken(&mut self, tree: SubTree) -> SubTree {
+        match tree {
+            (Some(root), height) if self.node(root).is_red() => {
+                self.set_color(root, Color::Black);
+                (Some(root), height + 1)
+            },
+            tree => tree,
+        }
+    }
+
+    // 部分木を、is_leftがtrueになるkeyを持つ木とfalseになるkeyを持つ木に分割する
+    // is_leftはkeyの順序に対して単調(trueの後にfalseが続く)である必要がある
+    fn split(&mut self, node: Option<NodeId>, height: usize, is_left: &dyn Fn(&K) -> bool) -> (SubTree, SubTree) {
+        let node = match node {
+            Some(node) => node,
+            None => return ((None, 0), (None, 0)),
+        };
+        // 子を切り離す前に、溜まっているupdateを子に適用しておく
+        self.push_down(node);
+        // 子の黒高さは、自分が黒なら1減る
+        let child_height = if self.node(node).is_black() { height - 1 } else { height };
+        let left = self.node_mut(node).left.take();
+        let right = self.node_mut(node).right.take();
+        if let Some(left) = left { self.node_mut(left).set_parent(None); }
+        if let Some(right) = right { self.node_mut(right).set_parent(None); }
+        self.node_mut(node).set_parent(None);
+        if is_left(&self.node(node).key) {
+            // 自分と左の部分木は左側に入るので、右の部分木を分割して左側に付け足す
+            let (middle, right) = self.split(right, child_height, is_left);
+            (self.join((left, child_height), node, middle), right)
+        } else {
+            // 自分と右の部分木は右側に入るので、左の部分木を分割して右側に付け足す
+            let (left, middle) = self.split(left, child_height, is_left);
+            (left, self.join(middle, node, (right, child_height)))
+        }
+    }
+
+    // 左の木の全てのkey < nodeのkey < 右の木の全てのkey となる2つの木をnodeを挟んで結合する
+    // 黒高さの差の分だけ降りるので、O(|左の黒高さ - 右の黒高さ| + 1)
+    fn join(&mut self, left: SubTree, node: NodeId, right: SubTree) -> SubTree {
+        let (left, left_height) = self.blacken(left);
+        let (right, right_height) = self.blacken(right);
+        self.node_mut(node).set_parent(None);
+        // 黒高さが同じ場合は、nodeを黒にして根にする
+        if left_height == right_height {
+            self.set_color(node, Color::Black);
+            self.node_mut(node).left = left;
+            self.node_mut(node).right = right;
+            if let Some(left) = left { self.node_mut(left).set_parent(Some(node)); }
+            if let Some(right) = right { self.node_mut(right).set_parent(Some(node)); }
+            self.update_node(node);
+            return (Some(node), left_height + 1);
+        }
+        // 高い方の木の端を降りて、低い方の木と同じ黒高さの黒ノードの位置に赤にしたnodeを置き、insertと同じように木を修正する
+        self.set_color(node, Color::Red);
+        let left_is_taller = left_height > right_height;
+        let (root, taller_height, shorter_height) = if left_is_taller {
+            (left, left_height, right_height)
+        } else {
+            (right, right_height, left_height)
+        };
+        let mut parent = None;
+        let mut current_node = root;
+        let mut height = taller_height;
+        while let Some(current) = current_node {
+            if self.node(current).is_black() {
+                if height == shorter_height { break; }
+                height -= 1;
+            }
+            // 通り過ぎるノードの下にnodeを置くので、溜まっているupdateを子に適用しておく
+            self.push_down(current);
+            parent = Some(current);
+            current_node = if left_is_taller { self.right(current) } else { self.left(current) };
+        }
+        // 高い方の木の根の黒高さは低い方より大きいので、parentは必ず存在する
+        let parent = parent.unwrap();
+        if left_is_taller {
+            // 左の木の右端にnodeを置き、見つけたノードをnodeの左の子、右の木をnodeの右の子にする
+            self.node_mut(node).left = current_node;
+            self.node_mut(node).right = right;
+            self.node_mut(parent).right = Some(node);
+        } else {
+            // 右の木の左端にnodeを置き、見つけたノードをnodeの右の子、左の木をnodeの左の子にする
+            self.node_mut(node).left = left;
+            self.node_mut(node).right = current_node;
+            self.node_mut(parent).left = Some(node);
+        }
+        self.node_mut(node).set_parent(Some(parent));
+        if let Some(left) = self.left(node) { self.node_mut(left).set_parent(Some(node)); }
+        if let Some(right) = self.right(node) { self.node_mut(right).set_parent(Some(node)); }
+        // nodeを置いた位置から根までの部分木のノード数とsummaryを更新する
+        self.update_nodes_to_root(Some(node));
+        // change_structureで根の左右の子が赤から黒に塗り替えられた場合(場合1で根まで登った場合)は、黒高さが1増える
+        let root_child = if left_is_taller { self.left(root.unwrap()) } else { self.right(root.unwrap()) };
+        let root_child_was_red = self.is_red(root_child);
+        // change_structureは木の根を書き換えるので、結合中の木の根に差し替えておく
+        let tree_root = mem::replace(&mut self.root, root);
+        self.change_structure(node);
+        let joined_root = mem::replace(&mut self.root, tree_root);
+        let grown = joined_root == root && root_child_was_red && self.is_black(root_child);
+        (joined_root, if grown { taller_height + 1 } else { taller_height })
+    }
+
+    // 間にノードを挟まずに2つの木を結合する。右の木のミニマムノードを取り出して、それを挟んでjoinする
+    fn join_without_node(&mut self, left: SubTree, right: SubTree) -> SubTree {
+        let right_root = match right.0 {
+            Some(right_root) => right_root,
+            None => return left,
+        };
+        let tree_root = self.root.replace(right_root);
+        let minimum_node = self.find_minimum(right_root);
+        self.delete_node(minimum_node);
+        let right_root = mem::replace(&mut self.root, tree_root);
+        let right_height = self.black_height(right_root);
+        self.join(left, minimum_node, (right_root, right_height))
+    }
+}
+
+// 回転と、挿入・削除の後の色の修正をノードの持ち方によらずに共有するためのtrait
+// RBTreeはslabの中のノードをNodeIdで辿り、IntrusiveRBTreeは利用者の構造体に埋め込まれたRBLinkをポインタで辿る
+trait Links {
+    type Node: Copy + Eq;
+
+    fn root(&self) -> Option<Self::Node>;
+    fn set_root(&mut self, root: Option<Self::Node>);
+    fn parent(&self, node: Self::Node) -> Option<Self::Node>;
+    fn set_parent(&mut self, node: Self::Node, parent: Option<Self::Node>);
+    fn left(&self, node: Self::Node) -> Option<Self::Node>;
+    fn set_left(&mut self, node: Self::Node, left: Option<Self::Node>);
+    fn right(&self, node: Self::Node) -> Option<Self::Node>;
+    fn set_right(&mut self, node: Self::Node, right: Option<Self::Node>);
+    fn color(&self, node: Self::Node) -> Color;
+    fn set_color(&mut self, node: Self::Node, color: Color);
+
+    // 部分木ごとの値(ノード数やsummary、溜まっているupdate)を持たない木では何もしない
+    fn push_down(&self, _node: Self::Node) {}
+
+    fn push_path(&self, _node: Self::Node) {}
+
+    fn update_node(&mut self, _node: Self::Node) {}
+
+    // nodeから根までの部分木のノード数とsummaryを計算し直す
+    fn update_nodes_to_root(&mut self, node: Option<Self::Node>) {
+        let mut current_node = node;
+        while let Some(node) = current_node {
+            self.update_node(node);
+            current_node = self.parent(node);
+        }
+    }
+
+    // 存在しないノード(葉)は黒とみなす
+    #[inline]
+    fn is_red(&self, node: Option<Self::Node>) -> bool {
+        node.is_some_and(|node| self.color(node) == Color::Red)
+    }
+
+    #[inline]
+    fn is_black(&self, node: Option<Self::Node>) -> bool {
+        !self.is_red(node)
+    }
+
+    fn minimum(&self, partial: Self::Node) -> Self::Node {
+        let mut node = partial;
+        self.push_down(node);
+        while let Some(left) = self.left(node) {
+            node = left;
+            self.push_down(node);
+        }
+        node
+    }
+
+    // 中間順で次のノードを返す
+    fn successor(&self, node: Self::Node) -> Option<Self::Node> {
+        if let Some(right) = self.right(node) {
+            self.push_down(node);
+            let mut node = right;
+            self.push_down(node);
+            while let Some(left) = self.left(node) {
+                node = left;
+                self.push_down(node);
+            }
+            return Some(node);
+        }
+        // 右の子が存在しない場合、自分が左の子になるまで登ってその親を返す
+        let mut node = node;
+        while let Some(parent) = self.parent(node) {
+            if self.left(parent) == Some(node) { return Some(parent); }
+            node = parent;
+        }
+        None
+    }
+
+    // 中間順で前のノードを返す
+    fn predecessor(&self, node: Self::Node) -> Option<Self::Node> {
+        if let Some(left) = self.left(node) {
+            self.push_down(node);
+            let mut node = left;
+            self.push_down(node);
+            while let Some(right) = self.right(node) {
+                node = right;
+                self.push_down(node);
+            }
+            return Some(node);
+        }
+        // 左の子が存在しない場合、自分が右の子になるまで登ってその親を返す
+        let mut node = node;
+        while let Some(parent) = self.parent(node) {
+            if self.right(parent) == Some(node) { return Some(parent); }
+            node = parent;
+        }
+        None
+    }
+
+    // 木からノードを切り離す。ノードの枠の解放とlenの更新は呼び出し側で行う
+    fn delete_node(&mut self, remove_node: Self::Node) {
+        // 付け替える部分木にupdateが残らないように、根から削除対象までのupdateを適用しておく
+        self.push_path(remove_node);
+        let mut origin_color = self.color(remove_node);
+        // fixupの対象は、
+        // 削除対象の左右どちらかの子ノードが存在しない場合は、昇格予定のノード
+        // 削除対象の左右どちらの子ノードも存在する場合は、削除対象の右側の部分木のミニマムノードの右の子ノード
+        // なので、fixupの対象が移動する前に存在していたノードの色が黒だった場合、fixupが発生する
+        // 赤だった場合は特にこれが黒になろうと赤のままであろうと性質4(赤が連続してはいけない) or 性質5(任意の葉までパスにおける黒のノードの数は同じ)に違反する可能性はない
+        // また、fixupの対象が赤の場合は確実に根ではないことが確定するので、性質2にも違反しない
+        let fixup_node: Option<Self::Node>;
+        // fixup_nodeが置かれる位置の親。fixup_nodeが存在しない(葉の)場合も、ここから兄弟を辿る
+        let fixup_parent: Option<Self::Node>;
+        if self.left(remove_node).is_none() { // 削除対象の左の子が存在していない場合、削除対象のところに右の子を持ってくる
+            fixup_node = self.right(remove_node);
+            fixup_parent = self.parent(remove_node);
+            // 削除対象の親の(右 or 左の)子を右の子にする
+            self.transparent(remove_node, fixup_node);
+        } else if self.right(remove_node).is_none() { // 左の子が存在して、右の子が存在していない場合、削除対象のところに左の子を持ってくる
+            fixup_node = self.left(remove_node);
+            fixup_parent = self.parent(remove_node);
+            // 削除対象の親の(右 or 左の)子を左の子にする
+            self.transparent(remove_node, fixup_node);
+        } else { // 右の子も左の子も存在した場合、削除対象の右部分木内でのミニマムなノードとそのミニマムノードの右の子を入れ替えた後で、削除対象の位置にミニマムノードを持ってくる
+            let minimum_node = self.minimum(self.right(remove_node).unwrap());
+            origin_color = self.color(minimum_node);
+            fixup_node = self.right(minimum_node);
+            // 削除対象がミニマムの親のNodeだった場合、ミニマムノードの右の子はそのままミニマムノードの右の子になる
+            if self.parent(minimum_node) == Some(remove_node) {
+                fixup_parent = Some(minimum_node);
+            } else { // 削除対象がミニマムの親じゃなかった場合
+                // ミニマムの位置にミニマムの右の子を持ってくる
+                fixup_parent = self.parent(minimum_node);
+                self.transparent(minimum_node, fixup_node);
+                // 削除対象ノードの右の子をミニマムの右の子にする
+                let right = self.right(remove_node);
+                self.set_right(minimum_node, right);
+                self.set_parent(right.unwrap(), Some(minimum_node));
+            }
+            // 削除対象の親の(右 or 左の)子をミニマムノードにする
+            self.transparent(remove_node, Some(minimum_node));
+            // 削除対象ノードとミニマムノードを入れ替えて、色も同じにする
+            let left = self.left(remove_node);
+            self.set_left(minimum_node, left);
+            self.set_parent(left.unwrap(), Some(minimum_node));
+            let color = self.color(remove_node);
+            self.set_color(minimum_node, color);
+        }
+        // ノードが抜けた位置から根までの部分木のノード数とsummaryを更新する
+        self.update_nodes_to_root(fixup_parent);
+        if origin_color == Color::Black {
+            // delete_fixupにて下記の場合を修正する
+            // 1) 削除対象ノードが根だった場合にfixup_nodeが赤ノードだった場合(性質2に違反する)
+            // 2) 削除対象ノードの親が赤ノードでfixup_nodeノードが赤だった場合(性質4に違反する)
+            // 3) 削除対象ノード or 削除対象ノードに左右の子ノードが存在し、削除対象ノードの部分木内のミニマムノードの色が黒の場合、黒ノードの数が1減ってしまう(性質5に違反)
+            // 性質5への違反は、新しく配置されたノードの色を特黒ノードとして、黒のノードが一つ追加されるとして計算すれば解消するが、この場合、性質1のノードが赤か黒であることに違反する
+            // このノードは赤黒か黒黒の状態となり、正し、属性は依然として赤か黒のどちらかとなっている。この状態をうまく修正する
+            self.delete_fixup(fixup_node, fixup_parent);
+        }
+    }
+
+    fn change_structure(&mut self, node: Self::Node) {
+        let mut current_node = node;
+        // 親が赤である限り続ける(親が赤の場合、親は根ではないので祖父は必ず存在する)
+        while let Some(parent) = self.parent(current_node) {
+            if self.is_black(Some(parent)) { break; }
+            let grand_parent = self.parent(parent).unwrap();
+            // 親が左の子の場合
+            if self.left(grand_parent) == Some(parent) {
+                // 場合1: 叔父が赤の場合、叔父と親を黒にして、祖父を赤にして二段登る
+                if let Some(uncle) = self.right(grand_parent).filter(|uncle| self.is_red(Some(*uncle))) {
+                    self.set_color(uncle, Color::Black);
+                    self.set_color(parent, Color::Black);
+                    self.set_color(grand_parent, Color::Red);
+                    current_node = grand_parent;
+                    continue;
+                }
+                // 叔父が存在しない場合は黒とみなす
+                // 場合2: 叔父が黒で自分が右の子の場合、親を左回転させる
+                if self.right(parent) == Some(current_node) {
+                    current_node = parent;
+                    self.rotate(current_node, Rotate::Left);
+                }
+                // 場合3: 叔父が黒で自分が左の子の場合、祖父を赤、親を黒にして、祖父を起点に右回転させる
+                self.set_color(grand_parent, Color::Red);
+                let parent = self.parent(current_node).unwrap();
+                self.set_color(parent, Color::Black);
+                self.rotate(grand_parent, Rotate::Right);
+            } else { // 親が右の子の場合
+                // 場合1: 叔父が赤の場合、叔父と親を黒にして、祖父を赤にして二段登る
+                if let Some(uncle) = self.left(grand_parent).filter(|uncle| self.is_red(Some(*uncle))) {
+                    self.set_color(uncle, Color::Black);
+                    self.set_color(parent, Color::Black);
+                    self.set_color(grand_parent, Color::Red);
+                    current_node = grand_parent;
+                    continue;
+                }
+                // 叔父が存在しない場合は黒とみなす
+                // 場合2: 叔父が黒で自分が左の子の場合、親を右回転させる
+                if self.left(parent) == Some(current_node) {
+                    current_node = parent;
+                    self.rotate(current_node, Rotate::Right);
+                }
+                // 場合3: 叔父が黒で自分が右の子の場合、祖父を赤、親を黒にして、祖父を起点に左回転させる
+                self.set_color(grand_parent, Color::Red);
+                let parent = self.parent(current_node).unwrap();
+                self.set_color(parent, Color::Black);
+                self.rotate(grand_parent, Rotate::Left);
+            }
+        }
+        let root = self.root().unwrap();
+        self.set_color(root, Color::Black);
+    }
+
+    fn rotate(&mut self, node: Self::Node, rotate: Rotate) {
+        // 回転で部分木の区切りが変わるので、自分と親になる子のupdateを先に子に適用しておく
+        self.push_down(node);
+        let child = if rotate == Rotate::Left { self.right(node) } else { self.left(node) };
+        let child = match child {
+            Some(child) => child,
+            None => return,
+        };
+        self.push_down(child);
+        let parent = self.parent(node);
+        if rotate == Rotate::Left {
+            let grand_child = self.left(child);
+            self.set_right(node, grand_child); // 右の子(今は親)の左の子を自分の右の子にする
+            if let Some(grand_child) = grand_child { self.set_parent(grand_child, Some(node)); } // 移動してきた右の子の親を自分にする
+            self.set_left(child, Some(node)); // 自分の親の左の子を自分にする
+        }
+        if rotate == Rotate::Right {
+            let grand_child = self.right(child);
+            self.set_left(node, grand_child); // 左の子(今は親)の右の子を自分の左の子にする
+            if let Some(grand_child) = grand_child { self.set_parent(grand_child, Some(node)); } // 移動してきた左の子の親を自分にする
+            self.set_right(child, Some(node)); // 自分の親の右の子を自分にする
+        }
+        self.set_parent(node, Some(child)); // 自分の親を元の子にする
+        self.set_parent(child, parent); // 元の子の親を自分の親にする
+
+        match parent {
+            Some(parent) => { // もともとの自分の親(今は祖父)に紐づく子を自分の元子(今は親)に紐付ける
+                if self.right(parent) == Some(node) {
+                    self.set_right(parent, Some(child));
+                } else {
+                    self.set_left(parent, Some(child));
+                }
+            },
+            None => self.set_root(Some(child)), // 回転した結果、子が根になった場合、treeの根を更新する
+        }
+        // 自分(今は子)、元の子(今は親)の順に部分木のノード数とsummaryを計算し直す
+        self.update_node(node);
+        self.update_node(child);
+    }
+
+    // 昇格する予定のNodeに対しての上から降るパスのみを更新する
+    // 昇格する予定のNodeからの上へのパスはすでに更新されていることが前提
+    fn transparent(&mut self, removal_node: Self::Node, promotion_node: Option<Self::Node>) {
+        let parent = self.parent(removal_node);
+        match parent {
+            // 根の場合
+            None => self.set_root(promotion_node),
+            Some(parent) => {
+                if self.left(parent) == Some(removal_node) {
+                    self.set_left(parent, promotion_node);
+                } else {
+                    self.set_right(parent, promotion_node);
+                }
+            },
+        }
+        if let Some(promotion_node) = promotion_node { self.set_parent(promotion_node, parent); }
+    }
+
+    // nodeが存在しない(葉の)場合も特黒として扱えるように、nodeが置かれている位置の親も受け取る
+    // 葉の場合も兄弟は確実に存在するので、親の左の子がnodeと等しいかどうかで左右を判定できる
+    fn delete_fixup(&mut self, promotion_node: Option<Self::Node>, promotion_parent: Option<Self::Node>) {
+        let mut node = promotion_node;
+        let mut parent = promotion_parent;
+        // 以下の条件のいづれかが成立するまで、木の中の特黒を持ち上げる
+        // 1) nodeが赤黒nodeを指す。この場合はwhileを抜けた後で普通の黒に彩色する
+        // 2) nodeが根を指す。この場合には単純に特黒を取り除く
+        // 3) 適切な回転と再彩色を行ってループを停止する
+        // このループの中ではnodeは常に根ではない黒黒を指す
+        while node != self.root() && self.is_black(node) {
+            let parent_node = parent.unwrap();
+            if self.left(parent_node) == node {
+                // nodeが特黒の場合、性質5のため兄弟は確実に存在している。
+                let mut brother = self.right(parent_node).unwrap();
+                // 場合1) 兄弟ノードが赤の場合、その子ノードは黒であり、兄弟ノードを黒、親ノードを赤に変更し、親ノードを左回転することで、場合2 or 場合3 or 場合4にする
+                // 場合2, 3, 4は兄弟ノードが黒の場合の対応である
+                if self.is_red(Some(brother)) {
+                    self.set_color(brother, Color::Black);
+                    self.set_color(parent_node, Color::Red);
+                    self.rotate(parent_node, Rotate::Left);
+                    // 兄弟ノードが赤の場合、その子ノードは確実に二つ存在し、色は黒である
+                    // これは、insert時に親と叔父が赤だった場合、祖父を赤にして、親と叔父を黒にすることによって性質4を解消する際に生じる状態？
+                    brother = self.right(parent_node).unwrap();
+                }
+                // 場合2) 兄弟ノードが黒かつ、兄弟ノードの子の両方が黒または存在しない or 片方が黒で片方が存在しない場合
+                if self.is_black(Some(brother)) && self.is_black(self.right(brother)) && self.is_black(self.left(brother)) {
+                    // 兄弟ノードを赤にしてから特黒ノードをを親にする
+                    // もし、特黒ノード(親)が赤だった場合その時点でループが終了する
+                    // 場合1を経てきた場合は上の条件を満たすので終了する
+                    self.set_color(brother, Color::Red);
+                    node = Some(parent_node);
+                    parent = self.parent(parent_node);
+                } else {
+                    // 場合3) 兄弟ノードが(葉 or 黒)かつ兄弟ノードの右の子が黒かつ兄弟ノードの左の子が赤
+                    if self.is_black(self.right(brother)) {
+                        // 兄弟の左の子の色を黒にし、兄弟ノードを赤にした上で兄弟ノードを起点に右回転し、元の兄弟ノードの位置に上がって来た兄弟ノードの左の子を兄弟ノードとする
+                        // これにより場合4に変換される
+                        let brother_left = self.left(brother).unwrap();
+                        self.set_color(brother_left, Color::Black);
+                        self.set_color(brother, Color::Red);
+                        self.rotate(brother, Rotate::Right);
+                        brother = self.parent(brother).unwrap();
+                    }
+                    // 場合4) 兄弟ノードが黒かつ、兄弟ノードの右の子が赤
+                    // 兄弟ノードの色を親ノードの色に変換し、親ノードの色を黒に、兄弟ノードの右の子の色を黒にし、親ノードを左回転させて、ノードをrootにしてループを停止する
+                    let color = self.color(parent_node);
+                    self.set_color(brother, color);
+                    self.set_color(parent_node, Color::Black);
+                    let brother_right = self.right(brother).unwrap();
+                    self.set_color(brother_right, Color::Black);
+                    self.rotate(parent_node, Rotate::Left);
+
+                    // 停止
+                    node = self.root();
+                    parent = None;
+                }
+            } else {
+                // nodeが特黒の場合、性質5のため兄弟は確実に存在している。
+                let mut brother = self.left(parent_node).unwrap();
+                // 場合1) 兄弟ノードが赤の場合、その子ノードは黒であり、兄弟ノードを黒、親ノードを赤に変更し、親ノードを右回転することで、場合2 or 場合3 or 場合4にする
+                // 場合2, 3, 4は兄弟ノードが黒の場合の対応である
+                if self.is_red(Some(brother)) {
+                    self.set_color(brother, Color::Black);
+                    self.set_color(parent_node, Color::Red);
+                    self.rotate(parent_node, Rotate::Right);
+                    // 兄弟ノードが赤の場合、その子ノードは確実に二つ存在し、色は黒である
+                    // これは、insert時に親と叔父が赤だった場合、祖父を赤にして、親と叔父を黒にすることによって性質4を解消する際に生じる状態？
+                    brother = self.left(parent_node).unwrap();
+                }
+                // 場合2) 兄弟ノードが黒かつ、兄弟ノードの子の両方が黒または存在しない or 片方が黒で片方が存在しない場合
+                if self.is_black(Some(brother)) && self.is_black(self.left(brother)) && self.is_black(self.right(brother)) {
+                    // 兄弟ノードを赤にしてから特黒ノードをを親にする
+                    // もし、特黒ノード(親)が赤だった場合その時点でループが終了する
+                    // 場合1を経てきた場合は上の条件を満たすので終了する
+                    self.set_color(brother, Color::Red);
+                    node = Some(parent_node);
+                    parent = self.parent(parent_node);
+                } else {
+                    // 場合3) 兄弟ノードが(葉 or 黒)かつ兄弟ノードの左の子が黒かつ兄弟ノードの右の子が赤
+                    if self.is_black(self.left(brother)) {
+                        // 兄弟の右の子の色を黒にし、兄弟ノードを赤にした上で兄弟ノードを起点に左回転し、元の兄弟ノードの位置に上がって来た兄弟ノードの右の子を兄弟ノードとする
+                        // これにより場合4に変換される
+                        let brother_right = self.right(brother).unwrap();
+                        self.set_color(brother_right, Color::Black);
+                        self.set_color(brother, Color::Red);
+                        self.rotate(brother, Rotate::Left);
+                        brother = self.parent(brother).unwrap();
+                    }
+                    // 場合4) 兄弟ノードが黒かつ、兄弟ノードの左の子が赤
+                    // 兄弟ノードの色を親ノードの色に変換し、親ノードの色を黒に、兄弟ノードの左の子の色を黒にし、親ノードを右回転させて、ノードをrootにしてループを停止する
+                    let color = self.color(parent_node);
+                    self.set_color(brother, color);
+                    self.set_color(parent_node, Color::Black);
+                    let brother_left = self.left(brother).unwrap();
+                    self.set_color(brother_left, Color::Black);
+                    self.rotate(parent_node, Rotate::Right);
+
+                    // 停止
+                    node = self.root();
+                    parent = None;
+                }
+            }
+        }
+        if let Some(node) = node { self.set_color(node, Color::Black); }
+    }
+}
+
+
+impl<K, V, A: Augment<K, V>, C: Compare<K>> Links for RBTree<K, V, A, C> {
+    type Node = NodeId;
+
+    #[inline]
+    fn root(&self) -> Option<NodeId> {
+        self.root
+    }
+
+    #[inline]
+    fn set_root(&mut self, root: Option<NodeId>) {
+        self.root = root;
+    }
+
+    #[inline]
+    fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.node(id).parent()
+    }
+
+    #[inline]
+    fn set_parent(&mut self, id: NodeId, parent: Option<NodeId>) {
+        self.node_mut(id).set_parent(parent);
+    }
+
+    #[inline]
+    fn left(&self, id: NodeId) -> Option<NodeId> {
+        self.node(id).left
+    }
+
+    #[inline]
+    fn set_left(&mut self, id: NodeId, left: Option<NodeId>) {
+        self.node_mut(id).left = left;
+    }
+
+    #[inline]
+    fn right(&self, id: NodeId) -> Option<NodeId> {
+        self.node(id).right
+    }
+
+    #[inline]
+    fn set_right(&mut self, id: NodeId, right: Option<NodeId>) {
+        self.node_mut(id).right = right;
+    }
+
+    #[inline]
+    fn color(&self, id: NodeId) -> Color {
+        self.node(id).get_color()
+    }
+
+    #[inline]
+    fn set_color(&mut self, id: NodeId, color: Color) {
+        self.node_mut(id).set_color(color);
+    }
+
+    // nodeに溜まっているupdateを左右の子に適用する
+    // HAS_UPDATEがfalseの木では何も書き換えないので、&selfで同時に読まれてもよい(Syncの前提)
+    fn push_down(&self, node: NodeId) {
+        if !A::HAS_UPDATE { return; }
+        let current_node = self.node(node);
+        if let Some(update) = unsafe { (*current_node.tag.get()).take() } {
+            if let Some(left) = current_node.left { self.apply_update(left, &update); }
+            if let Some(right) = current_node.right { self.apply_update(right, &update); }
+        }
+    }
+
+    // 根からnodeまでのパス上のノードに溜まっているupdateを、上から順に子に適用する
+    fn push_path(&self, node: NodeId) {
+        if let Some(parent) = self.parent(node) { self.push_path(parent); }
+        self.push_down(node);
+    }
+
+    // 子の部分木のノード数とsummaryから自分の部分木のノード数とsummaryを計算し直す
+    fn update_node(&mut self, node: NodeId) {
+        let current_node = self.node(node);
+        let size = self.size(current_node.left) + self.size(current_node.right) + 1;
+        let summary = A::combine(
+            &self.summary(current_node.left),
+            (&current_node.key, current_node.value()),
+            &self.summary(current_node.right),
+        );
+        let current_node = self.node_mut(node);
+        current_node.size = size as u32;
+        *current_node.summary.get_mut() = summary;
+    }
+}
+
+
+// valueを書き換えられるようにするメソッド
+// summaryを持つ木ではvalueを書き換えるとsummaryが古くなるので、集約しない木だけで使える
+impl<K, V, C: Compare<K>> RBTree<K, V, (), C> {
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+        where K: Borrow<Q>, Q: ?Sized, C: Compare<Q> {
+        let node = self.search(key)?;
+        Some(self.node_mut(node).value_mut())
+    }
+
+    pub fn get_mut_by_handle(&mut self, handle: NodeHandle) -> Option<&mut V> {
+        let node = self.handle_node(handle)?;
+        Some(self.node_mut(node).value_mut())
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V, C> {
+        let Iter { front, back, len, .. } = self.iter();
+        IterMut { tree: self, front, back, len, marker: PhantomData }
+    }
+
+    pub fn values_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut V> + ExactSizeIterator + '_ {
+        self.iter_mut().map(|(_, value)| value)
+    }
+
+    pub fn range_mut<Q, R>(&mut self, range: R) -> IterMut<'_, K, V, C>
+        where K: Borrow<Q>, Q: ?Sized, C: Compare<Q>, R: RangeBounds<Q> {
+        let Iter { front, back, len, .. } = self.range(range);
+        IterMut { tree: self, front, back, len, marker: PhantomData }
+    }
+}
+
+// BTreeMapは昇順に並んでいるので、そのまま並べてO(n)で組み立てる
+impl<K: Ord, V, A: Augment<K, V>> From<BTreeMap<K, V>> for RBTree<K, V, A> {
+    fn from(map: BTreeMap<K, V>) -> RBTree<K, V, A> {
+        RBTree::from_sorted_iter(map)
+    }
+}
+
+// BTreeMapも昇順に並んだ要素からはO(n)で組み立てる
+// KのOrdと違う順序を持つ木の場合は、BTreeMapの中で並べ直される
+impl<K: Ord, V, A: Augment<K, V>, C: Compare<K>> From<RBTree<K, V, A, C>> for BTreeMap<K, V> {
+    fn from(mut tree: RBTree<K, V, A, C>) -> BTreeMap<K, V> {
+        tree.take_sorted().into_iter().collect()
+    }
+}
+
+// ノードとvalueを全てコピーする。slabをそのままコピーするので、空いている枠の位置も同じになる
+impl<K: Clone, V: Clone, A: Augment<K, V>, C: Compare<K>> Clone for RBTree<K, V, A, C> {
+    fn clone(&self) -> RBTree<K, V, A, C> {
+        RBTree {
+            nodes: self.nodes.clone(),
+            free: self.free,
+            root: self.root,
+            len: self.len,
+            cmp: self.cmp.clone(),
+            generations: self.generations.clone(),
+            generation_base: self.generation_base,
+        }
+    }
+}
+
+impl<K, V, A: Augment<K, V>, C: Compare<K> + Default> Default for RBTree<K, V, A, C> {
+    fn default() -> RBTree<K, V, A, C> {
+        RBTree::with_comparator(C::default())
+    }
+}
+
+// UnsafeCellを持つので自動ではSyncにならないが、updateを溜めない(NoUpdateを実装したAugmentの)木は&selfのメソッドでノードを書き換えないので、
+// keyとvalueとsummaryと比較方法を複数のスレッドから同時に読めればSyncにできる
+// updateを溜める木は読み取りの途中でも子にupdateを適用するのでSyncにはせず、Mutexに入れて共有する
+// Sendは、木がslabの中のノードを所有していて添字で繋いでいるので、K、V、Cなどが全てSendなら自動で付く
+unsafe impl<K: Sync, V: Sync, A: NoUpdate<K, V>, C: Compare<K> + Sync> Sync for RBTree<K, V, A, C>
+    where A::Summary: Sync, A::Update: Sync {}
+
+// 比較はkeyの順に並べた(key, value)の列に対して行う。keyはKのOrdではなく木の順序で比べる
+impl<K, V, A: Augment<K, V>, C: Compare<K>> RBTree<K, V, A, C> {
+    fn compare_entries<F: FnMut(&V, &V) -> Option<Ordering>>(&self, other: &RBTree<K, V, A, C>, mut compare_value: F) -> Option<Ordering> {
+        let mut other_iter = other.iter();
+        for (key, value) in self.iter() {
+            let (other_key, other_value) = match other_iter.next() {
+                Some(entry) => entry,
+                None => return Some(Ordering::Greater),
+            };
+            match self.cmp.compare(key, other_key) {
+                Ordering::Equal => {},
+                ordering => return Some(ordering),
+            }
+            match compare_value(value, other_value)? {
+                Ordering::Equal => {},
+                ordering => return Some(ordering),
+            }
+        }
+        if other_iter.len() == 0 { Some(Ordering::Equal) } else { Some(Ordering::Less) }
+    }
+}
+
+impl<K, V: PartialEq, A: Augment<K, V>, C: Compare<K>> PartialEq for RBTree<K, V, A, C> {
+    fn eq(&self, other: &RBTree<K, V, A, C>) -> bool {
+        self.len == other.len && self.iter().zip(other.iter()).all(|((key, value), (other_key, other_value))| {
+            self.cmp.compare(key, other_key) == Ordering::Equal && value == other_value
+        })
+    }
+}
+
+impl<K, V: Eq, A: Augment<K, V>, C: Compare<K>> Eq for RBTree<K, V, A, C> {}
+
+impl<K, V: PartialOrd, A: Augment<K, V>, C: Compare<K>> PartialOrd for RBTree<K, V, A, C> {
+    fn partial_cmp(&self, other: &RBTree<K, V, A, C>) -> Option<Ordering> {
+        self.compare_entries(other, |a, b| a.partial_cmp(b))
+    }
+}
+
+impl<K, V: Ord, A: Augment<K, V>, C: Compare<K>> Ord for RBTree<K, V, A, C> {
+    fn cmp(&self, other: &RBTree<K, V, A, C>) -> Ordering {
+        self.compare_entries(other, |a, b| Some(a.cmp(b))).unwrap()
+    }
+}
+
+// BTreeMapと同じく要素数を先に入れて、木を並べてhashした時に区切りの違う列が同じにならないようにする
+// 等しいかどうかは木の順序で決まるので、cmpで等しいkeyはhashも等しくなっていること
+impl<K: Hash, V: Hash, A: Augment<K, V>, C: Compare<K>> Hash for RBTree<K, V, A, C> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len);
+        for entry in self.iter() {
+            entry.hash(state);
+        }
+    }
+}
+
+// 同じkeyがすでに存在する場合はvalueを上書きする
+impl<K, V, A: Augment<K, V>, C: Compare<K>> Extend<(K, V)> for RBTree<K, V, A, C> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, entries: I) {
+        for (key, value) in entries {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<'a, K: Copy, V: Copy, A: Augment<K, V>, C: Compare<K>> Extend<(&'a K, &'a V)> for RBTree<K, V, A, C> {
+    fn extend<I: IntoIterator<Item = (&'a K, &'a V)>>(&mut self, entries: I) {
+        self.extend(entries.into_iter().map(|(key, value)| (*key, *value)));
+    }
+}
+
+impl<K, V, A: Augment<K, V>, C: Compare<K> + Default> FromIterator<(K, V)> for RBTree<K, V, A, C> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(entries: I) -> RBTree<K, V, A, C> {
+        let mut tree = RBTree::default();
+        tree.extend(entries);
+        tree
+    }
+}
+
+impl<K, V, A: Augment<K, V>, C: Compare<K> + Default, const N: usize> From<[(K, V); N]> for RBTree<K, V, A, C> {
+    fn from(entries: [(K, V); N]) -> RBTree<K, V, A, C> {
+        IntoIterator::into_iter(entries).collect()
+    }
+}
+
+// 存在しないkeyの場合はpanicする
+impl<K, Q: ?Sized, V, A: Augment<K, V>, C: Compare<K> + Compare<Q>> Index<&Q> for RBTree<K, V, A, C>
+    where K: Borrow<Q> {
+    type Output = V;
+
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("no entry found for key")
+    }
+}
+
+pub struct Iter<'a, K, V, A: Augment<K, V> = (), C = Natural> {
+    tree: &'a RBTree<K, V, A, C>,
+    front: Option<NodeId>,
+    back: Option<NodeId>,
+    // まだ返していない要素の数。0になったら前後から辿ってきたノードが出会っている
+    len: usize,
+}
+
+impl<'a, K, V, A: Augment<K, V>, C: Compare<K>> Iterator for Iter<'a, K, V, A, C> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        if self.len == 0 { return None; }
+        let tree = self.tree;
+        let node = self.front?;
+        self.len -= 1;
+        self.front = tree.successor(node);
+        let node = tree.node(node);
+        Some((&node.key, node.value()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, K, V, A: Augment<K, V>, C: Compare<K>> DoubleEndedIterator for Iter<'a, K, V, A, C> {
+    fn next_back(&mut self) -> Option<(&'a K, &'a V)> {
+        if self.len == 0 { return None; }
+        let tree = self.tree;
+        let node = self.back?;
+        self.len -= 1;
+        self.back = tree.predecessor(node);
+        let node = tree.node(node);
+        Some((&node.key, node.value()))
+    }
+}
+
+impl<'a, K, V, A: Augment<K, V>, C: Compare<K>> ExactSizeIterator for Iter<'a, K, V, A, C> {}
+
+impl<'a, K, V, A: Augment<K, V>, C: Compare<K>> FusedIterator for Iter<'a, K, V, A, C> {}
+
+// 木を&mutで借りている間だけ作れるので、他にvalueを参照しているものはない
+pub struct IterMut<'a, K, V, C = Natural> {
+    tree: &'a RBTree<K, V, (), C>,
+    front: Option<NodeId>,
+    back: Option<NodeId>,
+    len: usize,
+    marker: PhantomData<&'a mut V>,
+}
+
+impl<'a, K, V, C: Compare<K>> IterMut<'a, K, V, C> {
+    // 各ノードは前後どちらかから1回しか返さないので、返した&mut Vが重なることはない
+    fn entry(&self, node: NodeId) -> (&'a K, &'a mut V) {
+        let node = self.tree.node(node);
+        (&node.key, unsafe { &mut *node.value.get() })
+    }
+}
+
+impl<'a, K, V, C: Compare<K>> Iterator for IterMut<'a, K, V, C> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a mut V)> {
+        if self.len == 0 { return None; }
+        let node = self.front?;
+        self.len -= 1;
+        self.front = self.tree.successor(node);
+        Some(self.entry(node))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, K, V, C: Compare<K>> DoubleEndedIterator for IterMut<'a, K, V, C> {
+    fn next_back(&mut self) -> Option<(&'a K, &'a mut V)> {
+        if self.len == 0 { return None; }
+        let node = self.back?;
+        self.len -= 1;
+        self.back = self.tree.predecessor(node);
+        Some(self.entry(node))
+    }
+}
+
+impl<'a, K, V, C: Compare<K>> ExactSizeIterator for IterMut<'a, K, V, C> {}
+
+impl<'a, K, V, C: Compare<K>> FusedIterator for IterMut<'a, K, V, C> {}
+
+// 最初に全ての要素を昇順に取り出しておくので、要素ごとに木を組み替えずに済む
+pub struct IntoIter<K, V, A = (), C = Natural> {
+    entries: vec::IntoIter<(K, V)>,
+    marker: PhantomData<fn() -> (A, C)>,
+}
+
+impl<K, V, A, C> Iterator for IntoIter<K, V, A, C> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        self.entries.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
+
+impl<K, V, A, C> DoubleEndedIterator for IntoIter<K, V, A, C> {
+    fn next_back(&mut self) -> Option<(K, V)> {
+        self.entries.next_back()
+    }
+}
+
+impl<K, V, A, C> ExactSizeIterator for IntoIter<K, V, A, C> {}
+
+impl<K, V, A, C> FusedIterator for IntoIter<K, V, A, C> {}
+
+impl<K, V, A: Augment<K, V>, C: Compare<K>> IntoIterator for RBTree<K, V, A, C> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V, A, C>;
+
+    fn into_iter(mut self) -> IntoIter<K, V, A, C> {
+        IntoIter { entries: self.take_sorted().into_iter(), marker: PhantomData }
+    }
+}
+
+impl<'a, K, V, C: Compare<K>> IntoIterator for &'a mut RBTree<K, V, (), C> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V, C>;
+
+    fn into_iter(self) -> IterMut<'a, K, V, C> {
+        self.iter_mut()
+    }
+}
+
+impl<'a, K, V, A: Augment<K, V>, C: Compare<K>> IntoIterator for &'a RBTree<K, V, A, C> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V, A, C>;
+
+    fn into_iter(self) -> Iter<'a, K, V, A, C> {
+        self.iter()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn len() {
+        let tree: RBTree<usize, usize> = RBTree::new();
+        assert_eq!(tree.len(), 0);
+    }
+
+    #[test]
+    fn insert() {
+        let mut tree: RBTree<usize, usize> = RBTree::new();
+        tree.insert(10, 2);
+        tree.insert(3, 1);
+        tree.insert(1, 2);
+        tree.insert(5, 3);
+        tree.insert(20, 4);
+        tree.insert(25, 5);
+        tree.insert(30, 5);
+        tree.insert(40, 6);
+        tree.insert(8, 6);
+        tree.insert(9, 6);
+        tree.insert(50, 6);
+        tree.insert(60, 6);
+        assert_eq!(tree.len(), 12);
+        // println!("{:#?}", tree);
+        assert_eq!(10, tree.root_node().unwrap().key);
+        assert_eq!(3, tree.root_node().unwrap().left().unwrap().key);
+        assert_eq!(25, tree.root_node().unwrap().right().unwrap().key);
+        assert_eq!(1, tree.root_node().unwrap().left().unwrap().left().unwrap().key);
+        println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().key, tree.root_node().unwrap().get_color());
+        println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().key, tree.root_node().unwrap().left().unwrap().get_color());
+        println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().key, tree.root_node().unwrap().right().unwrap().get_color());
+        println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().left().unwrap().key, tree.root_node().unwrap().left().unwrap().left().unwrap().get_color());
+        println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().right().unwrap().key, tree.root_node().unwrap().left().unwrap().right().unwrap().get_color());
+        println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().left().unwrap().key, tree.root_node().unwrap().right().unwrap().left().unwrap().get_color());
+        println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().right().unwrap().key, tree.root_node().unwrap().right().unwrap().right().unwrap().get_color());
+        println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().right().unwrap().left().unwrap().key, tree.root_node().unwrap().left().unwrap().right().unwrap().left().unwrap().get_color());
+        println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().right().unwrap().right().unwrap().key, tree.root_node().unwrap().left().unwrap().right().unwrap().right().unwrap().get_color());
+        println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().right().unwrap().left().unwrap().key, tree.root_node().unwrap().right().unwrap().right().unwrap().left().unwrap().get_color());
+        println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().right().unwrap().right().unwrap().key, tree.root_node().unwrap().right().unwrap().right().unwrap().right().unwrap().get_color());
+        println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().right().unwrap().right().unwrap().right().unwrap().key, tree.root_node().unwrap().right().unwrap().right().unwrap().right().unwrap().right().unwrap().get_color());
+    }
+
+    #[test]
+    fn find_minimum() {
+        let mut tree: RBTree<usize, usize> = RBTree::new();
+        tree.insert(10, 2);
+        let node = tree.find_minimum(tree.root.unwrap());
+        assert_eq!(10, tree.node(node).key);
+        println!("find_minimum, key: {:#?}", tree.node(node).key);
+        tree.insert(3, 1);
+        let node = tree.find_minimum(tree.root.unwrap());
+        assert_eq!(3, tree.node(node).key);
+        println!("find_minimum, key: {:#?}", tree.node(node).key);
+        tree.insert(1, 2);
+        tree.insert(5, 3);
+        tree.insert(20, 4);
+        tree.insert(25, 5);
+        tree.insert(30, 5);
+        tree.insert(40, 6);
+        tree.insert(8, 6);
+        tree.insert(9, 6);
+        tree.insert(50, 6);
+        tree.insert(60, 6);
+        let node = tree.find_minimum(tree.root.unwrap());
+        assert_eq!(1, tree.node(node).key);
+        println!("find_minimum, key: {:#?}", tree.node(node).key);
+
+        let node = tree.find_minimum(tree.root_node().unwrap().left().unwrap().right.unwrap());
+        assert_eq!(5, tree.node(node).key);
+        println!("find_minimum, key: {:#?}", tree.node(node).key);
+
+        let node = tree.find_minimum(tree.root_node().unwrap().right.unwrap());
+        assert_eq!(20, tree.node(node).key);
+        println!("find_minimum, key: {:#?}", tree.node(node).key);
+
+        let node = tree.find_minimum(tree.root_node().unwrap().right().unwrap().right.unwrap());
+        assert_eq!(30, tree.node(node).key);
+        println!("find_minimum, key: {:#?}", tree.node(node).key);
+
+        let node = tree.find_minimum(tree.root_node().unwrap().right().unwrap().right().unwrap().right.unwrap());
+        assert_eq!(50, tree.node(node).key);
+        println!("find_minimum, key: {:#?}", tree.node(node).key);
+    }
+
+    #[test]
+    fn find() {
+        let mut tree: RBTree<usize, usize> = RBTree::new();
+        let node = tree.search(&15);
+        assert_eq!(None, node);
+
+        tree.insert(10, 2);
+        tree.insert(3, 1);
+        tree.insert(1, 2);
+        tree.insert(5, 3);
+        tree.insert(20, 4);
+        tree.insert(25, 5);
+        tree.insert(30, 5);
+        tree.insert(40, 6);
+        tree.insert(8, 6);
+        tree.insert(9, 6);
+        tree.insert(50, 6);
+        tree.insert(60, 6);
+        let node = tree.search(&3);
+        assert_eq!(3, tree.node(node.unwrap()).key);
+        assert_eq!(Color::Black, tree.node(node.unwrap()).get_color());
+
+        let node = tree.search(&40);
+        assert_eq!(40, tree.node(node.unwrap()).key);
+        assert_eq!(Color::Red, tree.node(node.unwrap()).get_color());
+
+        let node = tree.search(&15);
+        assert_eq!(None, node);
+    }
+
+    #[test]
+    fn remove() {
+        let mut tree: RBTree<usize, usize> = RBTree::new();
+        tree.insert(10, 2);
+        tree.remove(&10).unwrap();
+        assert_eq!(0, tree.len());
+
+        tree.insert(10, 2);
+        tree.insert(3, 1);
+        tree.remove(&10).unwrap();
+        assert_eq!(1, tree.len());
+        assert_eq!(3, tree.root_node().unwrap().key);
+        assert_eq!(Color::Black, tree.root_node().unwrap().get_color());
+
+        tree.insert(1, 2);
+        tree.insert(5, 3);
+        tree.insert(20, 4);
+        tree.insert(25, 5);
+        tree.insert(30, 5);
+        tree.insert(40, 6);
+        tree.insert(8, 6);
+        tree.insert(9, 6);
+        tree.insert(50, 6);
+        tree.insert(60, 6);
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().key, tree.root_node().unwrap().get_color());
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().key, tree.root_node().unwrap().left().unwrap().get_color());
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().key, tree.root_node().unwrap().right().unwrap().get_color());
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().left().unwrap().key, tree.root_node().unwrap().left().unwrap().left().unwrap().get_color());
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().right().unwrap().key, tree.root_node().unwrap().left().unwrap().right().unwrap().get_color());
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().left().unwrap().key, tree.root_node().unwrap().right().unwrap().left().unwrap().get_color());
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().right().unwrap().key, tree.root_node().unwrap().right().unwrap().right().unwrap().get_color());
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().right().unwrap().left().unwrap().key, tree.root_node().unwrap().left().unwrap().right().unwrap().left().unwrap().get_color());
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().right().unwrap().left().unwrap().key, tree.root_node().unwrap().right().unwrap().right().unwrap().left().unwrap().get_color());
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().right().unwrap().right().unwrap().key, tree.root_node().unwrap().right().unwrap().right().unwrap().right().unwrap().get_color());
+
+        // 削除の場合3 + delete_fixupの場合4
+        tree.remove(&20).unwrap();
+        assert_eq!(10, tree.len());
+
+        // println!(" ======= ");
+        // println!(" remove 10 ");
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().key, tree.root_node().unwrap().get_color());
+        assert_eq!(25, tree.root_node().unwrap().key);
+        assert_eq!(Color::Black, tree.root_node().unwrap().get_color());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().key, tree.root_node().unwrap().left().unwrap().get_color());
+        assert_eq!(3, tree.root_node().unwrap().left().unwrap().key);
+        assert_eq!(Color::Red, tree.root_node().unwrap().left().unwrap().get_color());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().key, tree.root_node().unwrap().right().unwrap().get_color());
+        assert_eq!(50, tree.root_node().unwrap().right().unwrap().key);
+        assert_eq!(Color::Red, tree.root_node().unwrap().right().unwrap().get_color());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().left().unwrap().key, tree.root_node().unwrap().left().unwrap().left().unwrap().get_color());
+        assert_eq!(1, tree.root_node().unwrap().left().unwrap().left().unwrap().key);
+        assert_eq!(Color::Black, tree.root_node().unwrap().left().unwrap().left().unwrap().get_color());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().right().unwrap().key, tree.root_node().unwrap().left().unwrap().right().unwrap().get_color());
+        assert_eq!(8, tree.root_node().unwrap().left().unwrap().right().unwrap().key);
+        assert_eq!(Color::Black, tree.root_node().unwrap().left().unwrap().right().unwrap().get_color());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().left().unwrap().key, tree.root_node().unwrap().right().unwrap().left().unwrap().get_color());
+        assert_eq!(30, tree.root_node().unwrap().right().unwrap().left().unwrap().key);
+        assert_eq!(Color::Black, tree.root_node().unwrap().right().unwrap().left().unwrap().get_color());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().right().unwrap().key, tree.root_node().unwrap().right().unwrap().right().unwrap().get_color());
+        assert_eq!(60, tree.root_node().unwrap().right().unwrap().right().unwrap().key);
+        assert_eq!(Color::Black, tree.root_node().unwrap().right().unwrap().right().unwrap().get_color());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().right().unwrap().left().unwrap().key, tree.root_node().unwrap().left().unwrap().right().unwrap().left().unwrap().get_color());
+        assert_eq!(5, tree.root_node().unwrap().left().unwrap().right().unwrap().left().unwrap().key);
+        assert_eq!(Color::Red, tree.root_node().unwrap().left().unwrap().right().unwrap().left().unwrap().get_color());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().right().unwrap().right().unwrap().key, tree.root_node().unwrap().left().unwrap().right().unwrap().right().unwrap().get_color());
+        assert_eq!(9, tree.root_node().unwrap().left().unwrap().right().unwrap().right().unwrap().key);
+        assert_eq!(Color::Red, tree.root_node().unwrap().left().unwrap().right().unwrap().right().unwrap().get_color());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().left().unwrap().right().unwrap().key, tree.root_node().unwrap().right().unwrap().left().unwrap().right().unwrap().get_color());
+        assert_eq!(40, tree.root_node().unwrap().right().unwrap().left().unwrap().right().unwrap().key);
+        assert_eq!(Color::Red, tree.root_node().unwrap().right().unwrap().left().unwrap().right().unwrap().get_color());
+
+        // 削除の場合1 + delete_fixupの場合4
+        tree.remove(&40).unwrap();
+        assert_eq!(9, tree.len());
+
+        // println!(" ======= ");
+        // println!(" remove 40 ");
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().key, tree.root_node().unwrap().get_color());
+        assert_eq!(25, tree.root_node().unwrap().key);
+        assert_eq!(Color::Black, tree.root_node().unwrap().get_color());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().key, tree.root_node().unwrap().left().unwrap().get_color());
+        assert_eq!(3, tree.root_node().unwrap().left().unwrap().key);
+        assert_eq!(Color::Red, tree.root_node().unwrap().left().unwrap().get_color());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().key, tree.root_node().unwrap().right().unwrap().get_color());
+        assert_eq!(50, tree.root_node().unwrap().right().unwrap().key);
+        assert_eq!(Color::Red, tree.root_node().unwrap().right().unwrap().get_color());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().left().unwrap().key, tree.root_node().unwrap().left().unwrap().left().unwrap().get_color());
+        assert_eq!(1, tree.root_node().unwrap().left().unwrap().left().unwrap().key);
+        assert_eq!(Color::Black, tree.root_node().unwrap().left().unwrap().left().unwrap().get_color());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().right().unwrap().key, tree.root_node().unwrap().left().unwrap().right().unwrap().get_color());
+        assert_eq!(8, tree.root_node().unwrap().left().unwrap().right().unwrap().key);
+        assert_eq!(Color::Black, tree.root_node().unwrap().left().unwrap().right().unwrap().get_color());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().left().unwrap().key, tree.root_node().unwrap().right().unwrap().left().unwrap().get_color());
+        assert_eq!(30, tree.root_node().unwrap().right().unwrap().left().unwrap().key);
+        assert_eq!(Color::Black, tree.root_node().unwrap().right().unwrap().left().unwrap().get_color());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().right().unwrap().key, tree.root_node().unwrap().right().unwrap().right().unwrap().get_color());
+        assert_eq!(60, tree.root_node().unwrap().right().unwrap().right().unwrap().key);
+        assert_eq!(Color::Black, tree.root_node().unwrap().right().unwrap().right().unwrap().get_color());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().right().unwrap().left().unwrap().key, tree.root_node().unwrap().left().unwrap().right().unwrap().left().unwrap().get_color());
+        assert_eq!(5, tree.root_node().unwrap().left().unwrap().right().unwrap().left().unwrap().key);
+        assert_eq!(Color::Red, tree.root_node().unwrap().left().unwrap().right().unwrap().left().unwrap().get_color());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().right().unwrap().right().unwrap().key, tree.root_node().unwrap().left().unwrap().right().unwrap().right().unwrap().get_color());
+        assert_eq!(9, tree.root_node().unwrap().left().unwrap().right().unwrap().right().unwrap().key);
+        assert_eq!(Color::Red, tree.root_node().unwrap().left().unwrap().right().unwrap().right().unwrap().get_color());
+
+
+        // 削除の場合1 + delete_fixupの場合4(左のケース)
+        tree.remove(&1).unwrap();
+        assert_eq!(8, tree.len());
+        // println!(" ======= ");
+        // println!(" remove 1 ");
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().key, tree.root_node().unwrap().get_color());
+        assert_eq!(25, tree.root_node().unwrap().key);
+        assert_eq!(Color::Black, tree.root_node().unwrap().get_color());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().key, tree.root_node().unwrap().left().unwrap().get_color());
+        assert_eq!(8, tree.root_node().unwrap().left().unwrap().key);
+        assert_eq!(Color::Red, tree.root_node().unwrap().left().unwrap().get_color());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().key, tree.root_node().unwrap().right().unwrap().get_color());
+        assert_eq!(50, tree.root_node().unwrap().right().unwrap().key);
+        assert_eq!(Color::Red, tree.root_node().unwrap().right().unwrap().get_color());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().left().unwrap().key, tree.root_node().unwrap().left().unwrap().left().unwrap().get_color());
+        assert_eq!(3, tree.root_node().unwrap().left().unwrap().left().unwrap().key);
+        assert_eq!(Color::Black, tree.root_node().unwrap().left().unwrap().left().unwrap().get_color());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().right().unwrap().key, tree.root_node().unwrap().left().unwrap().right().unwrap().get_color());
+        assert_eq!(9, tree.root_node().unwrap().left().unwrap().right().unwrap().key);
+        assert_eq!(Color::Black, tree.root_node().unwrap().left().unwrap().right().unwrap().get_color());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().left().unwrap().key, tree.root_node().unwrap().right().unwrap().left().unwrap().get_color());
+        assert_eq!(30, tree.root_node().unwrap().right().unwrap().left().unwrap().key);
+        assert_eq!(Color::Black, tree.root_node().unwrap().right().unwrap().left().unwrap().get_color());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().right().unwrap().key, tree.root_node().unwrap().right().unwrap().right().unwrap().get_color());
+        assert_eq!(60, tree.root_node().unwrap().right().unwrap().right().unwrap().key);
+        assert_eq!(Color::Black, tree.root_node().unwrap().right().unwrap().right().unwrap().get_color());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().left().unwrap().right().unwrap().key, tree.root_node().unwrap().left().unwrap().left().unwrap().right().unwrap().get_color());
+        assert_eq!(5, tree.root_node().unwrap().left().unwrap().left().unwrap().right().unwrap().key);
+        assert_eq!(Color::Red, tree.root_node().unwrap().left().unwrap().left().unwrap().right().unwrap().get_color());
+
+
+        // 削除の場合1 + delete_fixupの場合 3 & 4(右のケース)を通る
+        tree.remove(&9).unwrap();
+        assert_eq!(7, tree.len());
+        // println!(" ======= ");
+        // println!(" remove 9 ");
+        // println!("parent node: {:#?}", tree.root_node().unwrap().clone());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().key, tree.root_node().unwrap().get_color());
+        assert_eq!(25, tree.root_node().unwrap().key);
+        assert_eq!(Color::Black, tree.root_node().unwrap().get_color());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().key, tree.root_node().unwrap().left().unwrap().get_color());
+        assert_eq!(5, tree.root_node().unwrap().left().unwrap().key);
+        assert_eq!(Color::Red, tree.root_node().unwrap().left().unwrap().get_color());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().key, tree.root_node().unwrap().right().unwrap().get_color());
+        assert_eq!(50, tree.root_node().unwrap().right().unwrap().key);
+        assert_eq!(Color::Red, tree.root_node().unwrap().right().unwrap().get_color());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().left().unwrap().key, tree.root_node().unwrap().left().unwrap().left().unwrap().get_color());
+        assert_eq!(3, tree.root_node().unwrap().left().unwrap().left().unwrap().key);
+        assert_eq!(Color::Black, tree.root_node().unwrap().left().unwrap().left().unwrap().get_color());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().right().unwrap().key, tree.root_node().unwrap().left().unwrap().right().unwrap().get_color());
+        assert_eq!(8, tree.root_node().unwrap().left().unwrap().right().unwrap().key);
+        assert_eq!(Color::Black, tree.root_node().unwrap().left().unwrap().right().unwrap().get_color());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().left().unwrap().key, tree.root_node().unwrap().right().unwrap().left().unwrap().get_color());
+        assert_eq!(30, tree.root_node().unwrap().right().unwrap().left().unwrap().key);
+        assert_eq!(Color::Black, tree.root_node().unwrap().right().unwrap().left().unwrap().get_color());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().right().unwrap().key, tree.root_node().unwrap().right().unwrap().right().unwrap().get_color());
+        assert_eq!(60, tree.root_node().unwrap().right().unwrap().right().unwrap().key);
+        assert_eq!(Color::Black, tree.root_node().unwrap().right().unwrap().right().unwrap().get_color());
+
+
+        let mut tree: RBTree<usize, usize> = RBTree::new();
+        tree.insert(10, 2);
+        tree.insert(3, 1);
+        tree.insert(1, 2);
+        tree.insert(5, 3);
+        tree.insert(20, 4);
+        tree.insert(25, 5);
+        tree.insert(30, 5);
+        tree.insert(40, 6);
+        tree.insert(8, 6);
+        tree.insert(9, 6);
+        tree.insert(50, 6);
+        tree.insert(60, 6);
+        assert_eq!(12, tree.len());
+
+        // println!(" ======= ");
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().key, tree.root_node().unwrap().get_color());
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().key, tree.root_node().unwrap().left().unwrap().get_color());
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().key, tree.root_node().unwrap().right().unwrap().get_color());
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().left().unwrap().key, tree.root_node().unwrap().left().unwrap().left().unwrap().get_color());
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().right().unwrap().key, tree.root_node().unwrap().left().unwrap().right().unwrap().get_color());
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().left().unwrap().key, tree.root_node().unwrap().right().unwrap().left().unwrap().get_color());
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().right().unwrap().key, tree.root_node().unwrap().right().unwrap().right().unwrap().get_color());
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().right().unwrap().left().unwrap().key, tree.root_node().unwrap().left().unwrap().right().unwrap().left().unwrap().get_color());
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().right().unwrap().right().unwrap().key, tree.root_node().unwrap().left().unwrap().right().unwrap().right().unwrap().get_color());
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().right().unwrap().left().unwrap().key, tree.root_node().unwrap().right().unwrap().right().unwrap().left().unwrap().get_color());
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().right().unwrap().right().unwrap().key, tree.root_node().unwrap().right().unwrap().right().unwrap().right().unwrap().get_color());
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().right().unwrap().right().unwrap().right().unwrap().key, tree.root_node().unwrap().right().unwrap().right().unwrap().right().unwrap().right().unwrap().get_color());
+
+        // 削除の場合1 + delete_fixupの場合 1 & 2(左のケース)を通る
+        tree.remove(&20).unwrap();
+        assert_eq!(11, tree.len());
+        // println!(" ======= ");
+        // println!(" remove 20 ");
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().key, tree.root_node().unwrap().get_color());
+        assert_eq!(10, tree.root_node().unwrap().key);
+        assert_eq!(Color::Black, tree.root_node().unwrap().get_color());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().key, tree.root_node().unwrap().left().unwrap().get_color());
+        assert_eq!(3, tree.root_node().unwrap().left().unwrap().key);
+        assert_eq!(Color::Black, tree.root_node().unwrap().left().unwrap().get_color());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().key, tree.root_node().unwrap().right().unwrap().get_color());
+        assert_eq!(40, tree.root_node().unwrap().right().unwrap().key);
+        assert_eq!(Color::Black, tree.root_node().unwrap().right().unwrap().get_color());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().left().unwrap().key, tree.root_node().unwrap().left().unwrap().left().unwrap().get_color());
+        assert_eq!(1, tree.root_node().unwrap().left().unwrap().left().unwrap().key);
+        assert_eq!(Color::Black, tree.root_node().unwrap().left().unwrap().left().unwrap().get_color());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().right().unwrap().key, tree.root_node().unwrap().left().unwrap().right().unwrap().get_color());
+        assert_eq!(8, tree.root_node().unwrap().left().unwrap().right().unwrap().key);
+        assert_eq!(Color::Black, tree.root_node().unwrap().left().unwrap().right().unwrap().get_color());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().left().unwrap().key, tree.root_node().unwrap().right().unwrap().left().unwrap().get_color());
+        assert_eq!(25, tree.root_node().unwrap().right().unwrap().left().unwrap().key);
+        assert_eq!(Color::Black, tree.root_node().unwrap().right().unwrap().left().unwrap().get_color());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().right().unwrap().key, tree.root_node().unwrap().right().unwrap().right().unwrap().get_color());
+        assert_eq!(50, tree.root_node().unwrap().right().unwrap().right().unwrap().key);
+        assert_eq!(Color::Black, tree.root_node().unwrap().right().unwrap().right().unwrap().get_color());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().right().unwrap().left().unwrap().key, tree.root_node().unwrap().left().unwrap().right().unwrap().left().unwrap().get_color());
+        assert_eq!(5, tree.root_node().unwrap().left().unwrap().right().unwrap().left().unwrap().key);
+        assert_eq!(Color::Red, tree.root_node().unwrap().left().unwrap().right().unwrap().left().unwrap().get_color());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().right().unwrap().right().unwrap().key, tree.root_node().unwrap().left().unwrap().right().unwrap().right().unwrap().get_color());
+        assert_eq!(9, tree.root_node().unwrap().left().unwrap().right().unwrap().right().unwrap().key);
+        assert_eq!(Color::Red, tree.root_node().unwrap().left().unwrap().right().unwrap().right().unwrap().get_color());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().left().unwrap().right().unwrap().key, tree.root_node().unwrap().right().unwrap().left().unwrap().right().unwrap().get_color());
+        assert_eq!(30, tree.root_node().unwrap().right().unwrap().left().unwrap().right().unwrap().key);
+        assert_eq!(Color::Red, tree.root_node().unwrap().right().unwrap().left().unwrap().right().unwrap().get_color());
+
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().right().unwrap().right().unwrap().key, tree.root_node().unwrap().right().unwrap().right().unwrap().right().unwrap().get_color());
+        assert_eq!(60, tree.root_node().unwrap().right().unwrap().right().unwrap().right().unwrap().key);
+        assert_eq!(Color::Red, tree.root_node().unwrap().right().unwrap().right().unwrap().right().unwrap().get_color());
+
+        // println!("node(25): {:#?}", tree.root_node().unwrap().right().unwrap().left().unwrap());
+        assert_eq!(None, tree.root_node().unwrap().right().unwrap().left().unwrap().left);
+
+
+        let mut tree: RBTree<usize, usize> = RBTree::new();
+        tree.insert(10, 2);
+        tree.insert(3, 1);
+        tree.insert(1, 2);
+        tree.insert(5, 3);
+        tree.insert(20, 4);
+        tree.insert(25, 5);
+        tree.insert(30, 5);
+        tree.insert(40, 6);
+        tree.insert(8, 6);
+        tree.insert(9, 6);
+        tree.insert(50, 6);
+        tree.insert(60, 6);
+        tree.insert(19, 6);
+        assert_eq!(13, tree.len());
+
+        // println!(" ======= ");
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().key, tree.root_node().unwrap().get_color());
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().key, tree.root_node().unwrap().left().unwrap().get_color());
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().key, tree.root_node().unwrap().right().unwrap().get_color());
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().left().unwrap().key, tree.root_node().unwrap().left().unwrap().left().unwrap().get_color());
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().right().unwrap().key, tree.root_node().unwrap().left().unwrap().right().unwrap().get_color());
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().left().unwrap().key, tree.root_node().unwrap().right().unwrap().left().unwrap().get_color());
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().right().unwrap().key, tree.root_node().unwrap().right().unwrap().right().unwrap().get_color());
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().right().unwrap().left().unwrap().key, tree.root_node().unwrap().left().unwrap().right().unwrap().left().unwrap().get_color());
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().right().unwrap().right().unwrap().key, tree.root_node().unwrap().left().unwrap().right().unwrap().right().unwrap().get_color());
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().right().unwrap().left().unwrap().key, tree.root_node().unwrap().right().unwrap().right().unwrap().left().unwrap().get_color());
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().right().unwrap().right().unwrap().key, tree.root_node().unwrap().right().unwrap().right().unwrap().right().unwrap().get_color());
+        // println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().right().unwrap().right().unwrap().right().unwrap().key, tree.root_node().unwrap().right().unwrap().right().unwrap().right().unwrap().right().unwrap().get_color());
+
+        // 削除の場合2 + delete_fixupなしで最後に黒になって終わる場合
+        tree.remove(&20).unwrap();
+        assert_eq!(12, tree.len());
+        println!(" ======= ");
+        println!(" remove 20 ");
+
+        println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().key, tree.root_node().unwrap().get_color());
+        assert_eq!(10, tree.root_node().unwrap().key);
+        assert_eq!(Color::Black, tree.root_node().unwrap().get_color());
+
+        println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().key, tree.root_node().unwrap().left().unwrap().get_color());
+        assert_eq!(3, tree.root_node().unwrap().left().unwrap().key);
+        assert_eq!(Color::Black, tree.root_node().unwrap().left().unwrap().get_color());
+
+        println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().key, tree.root_node().unwrap().right().unwrap().get_color());
+        assert_eq!(25, tree.root_node().unwrap().right().unwrap().key);
+        assert_eq!(Color::Black, tree.root_node().unwrap().right().unwrap().get_color());
+
+        println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().left().unwrap().key, tree.root_node().unwrap().left().unwrap().left().unwrap().get_color());
+        assert_eq!(1, tree.root_node().unwrap().left().unwrap().left().unwrap().key);
+        assert_eq!(Color::Black, tree.root_node().unwrap().left().unwrap().left().unwrap().get_color());
+
+        println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().right().unwrap().key, tree.root_node().unwrap().left().unwrap().right().unwrap().get_color());
+        assert_eq!(8, tree.root_node().unwrap().left().unwrap().right().unwrap().key);
+        assert_eq!(Color::Black, tree.root_node().unwrap().left().unwrap().right().unwrap().get_color());
+
+        println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().left().unwrap().key, tree.root_node().unwrap().right().unwrap().left().unwrap().get_color());
+        assert_eq!(19, tree.root_node().unwrap().right().unwrap().left().unwrap().key);
+        assert_eq!(Color::Black, tree.root_node().unwrap().right().unwrap().left().unwrap().get_color());
+
+        println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().right().unwrap().key, tree.root_node().unwrap().right().unwrap().right().unwrap().get_color());
+        assert_eq!(40, tree.root_node().unwrap().right().unwrap().right().unwrap().key);
+        assert_eq!(Color::Red, tree.root_node().unwrap().right().unwrap().right().unwrap().get_color());
+
+        println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().right().unwrap().left().unwrap().key, tree.root_node().unwrap().left().unwrap().right().unwrap().left().unwrap().get_color());
+        assert_eq!(5, tree.root_node().unwrap().left().unwrap().right().unwrap().left().unwrap().key);
+        assert_eq!(Color::Red, tree.root_node().unwrap().left().unwrap().right().unwrap().left().unwrap().get_color());
+
+        println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().left().unwrap().right().unwrap().right().unwrap().key, tree.root_node().unwrap().left().unwrap().right().unwrap().right().unwrap().get_color());
+        assert_eq!(9, tree.root_node().unwrap().left().unwrap().right().unwrap().right().unwrap().key);
+        assert_eq!(Color::Red, tree.root_node().unwrap().left().unwrap().right().unwrap().right().unwrap().get_color());
+
+        println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().right().unwrap().left().unwrap().key, tree.root_node().unwrap().right().unwrap().right().unwrap().left().unwrap().get_color());
+        assert_eq!(30, tree.root_node().unwrap().right().unwrap().right().unwrap().left().unwrap().key);
+        assert_eq!(Color::Black, tree.root_node().unwrap().right().unwrap().right().unwrap().left().unwrap().get_color());
+
+        println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().right().unwrap().right().unwrap().key, tree.root_node().unwrap().right().unwrap().right().unwrap().right().unwrap().get_color());
+        assert_eq!(50, tree.root_node().unwrap().right().unwrap().right().unwrap().right().unwrap().key);
+        assert_eq!(Color::Black, tree.root_node().unwrap().right().unwrap().right().unwrap().right().unwrap().get_color());
+
+        println!("key: {:#?}, color: {:?}", tree.root_node().unwrap().right().unwrap().right().unwrap().right().unwrap().right().unwrap().key, tree.root_node().unwrap().right().unwrap().right().unwrap().right().unwrap().right().unwrap().get_color());
+        assert_eq!(60, tree.root_node().unwrap().right().unwrap().right().unwrap().right().unwrap().right().unwrap().key);
+        assert_eq!(Color::Red, tree.root_node().unwrap().right().unwrap().right().unwrap().right().unwrap().right().unwrap().get_color());
+    }
+
+    #[test]
+    fn remove_range() {
+        let mut tree: RBTree<usize, usize> = RBTree::new();
+        for key in 1..=30 {
+            tree.insert(key, 1);
+        }
+        let removed = tree.remove_range(10..20);
+        assert_eq!(20, tree.len());
+        assert_eq!(10, removed.len());
+        assert_eq!((1..10).chain(20..=30).collect::<Vec<_>>(), keys(&tree));
+        assert_eq!((10..20).collect::<Vec<_>>(), keys(&removed));
+        assert_rb_tree(&tree);
+        assert_rb_tree(&removed);
+
+        let removed = tree.remove_range(25..=30);
+        assert_eq!((1..10).chain(20..25).collect::<Vec<_>>(), keys(&tree));
+        assert_eq!((25..=30).collect::<Vec<_>>(), keys(&removed));
+        assert_rb_tree(&tree);
+        assert_rb_tree(&removed);
+
+        let removed = tree.remove_range(..5);
+        assert_eq!((5..10).chain(20..25).collect::<Vec<_>>(), keys(&tree));
+        assert_eq!((1..5).collect::<Vec<_>>(), keys(&removed));
+        assert_rb_tree(&tree);
+        assert_rb_tree(&removed);
+
+        // 範囲内にkeyが存在しない場合は空の木を返す
+        let removed = tree.remove_range(10..20);
+        assert_eq!(10, tree.len());
+        assert_eq!(0, removed.len());
+        assert_eq!(None, removed.root);
+
+        let removed = tree.remove_range(..);
+        assert_eq!(0, tree.len());
+        assert_eq!(None, tree.root);
+        assert_eq!((5..10).chain(20..25).collect::<Vec<_>>(), keys(&removed));
+        assert_rb_tree(&removed);
+    }
+
+    #[test]
+    fn borrowed_key() {
+        let mut tree: RBTree<String, usize> = (0..50).map(|key| (format!("key-{:02}", key), key)).collect();
+        assert_eq!(Some(&10), tree.get("key-10"));
+        assert_eq!(Some(&tree.node(tree.search("key-20").unwrap()).key), tree.get_key_value("key-20").map(|(key, _)| key));
+        assert!(tree.search("key-50").is_none());
+        assert_eq!(vec![&48, &49], tree.range::<str, _>((Bound::Included("key-48"), Bound::Unbounded)).map(|(_, value)| value).collect::<Vec<_>>());
+        assert_eq!(5, tree.rank("key-05"));
+        assert_eq!(Some(30), tree.remove("key-30"));
+        assert!(!tree.contains_key("key-30"));
+        assert_rb_tree(&tree);
+    }
+
+    #[test]
+    fn prefix_range() {
+        // (tenant, timestamp)
+        let mut tree: RBTree<(String, u64), usize> = RBTree::new();
+        for (value, tenant) in ["b", "a", "c", "b", "a", "b"].iter().enumerate() {
+            tree.insert((tenant.to_string(), 100 - value as u64), value);
+        }
+        let entries = |tree: &RBTree<(String, u64), usize>, tenant: &str| {
+            tree.prefix_range(tenant).map(|((_, timestamp), value)| (*timestamp, *value)).collect::<Vec<_>>()
+        };
+        assert_eq!(vec![(96, 4), (99, 1)], entries(&tree, "a"));
+        assert_eq!(vec![(95, 5), (97, 3), (100, 0)], entries(&tree, "b"));
+        assert_eq!(3, tree.prefix_range("b").len());
+        assert_eq!(vec![(98, 2)], entries(&tree, "c"));
+        assert!(entries(&tree, "").is_empty());
+        assert!(entries(&tree, "ab").is_empty());
+        assert!(entries(&tree, "d").is_empty());
+        assert_eq!(Some((&("b".to_string(), 100), &0)), tree.prefix_range("b").next_back());
+
+        // 先頭の要素はそのままの型でも比較できる
+        let tree: RBTree<(u32, u32, u32), ()> = (0..1000).map(|key| ((key % 7, key % 3, key), ())).collect();
+        for prefix in 0..8 {
+            let expected: Vec<_> = keys(&tree).into_iter().filter(|(first, _, _)| *first == prefix).collect();
+            assert_eq!(expected, tree.prefix_range(&prefix).map(|(key, _)| *key).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn prefix() {
+        let words = ["car", "card", "care", "cart", "cat", "ca", "dog", "c", "cards", "caré", "carè"];
+        let tree: RBTree<String, usize> = words.iter().enumerate().map(|(value, word)| (word.to_string(), value)).collect();
+        let prefixed = |prefix: &str| tree.prefix(prefix).map(|(key, _)| key.as_str()).collect::<Vec<_>>();
+        assert_eq!(vec!["car", "card", "cards", "care", "cart", "carè", "caré"], prefixed("car"));
+        assert_eq!(vec!["card", "cards"], prefixed("card"));
+        assert_eq!(vec!["carè"], prefixed("carè"));
+        assert_eq!(vec!["dog"], prefixed("d"));
+        assert!(prefixed("carda").is_empty());
+        assert!(prefixed("b").is_empty());
+        assert!(prefixed("e").is_empty());
+        assert_eq!(words.len(), prefixed("").len());
+        assert_eq!(Some((&"caré".to_string(), &9)), tree.prefix("car").next_back());
+
+        // 先頭部分を短くしながら探す場合と比べる
+        let naive = |key: &str| (0..=key.len()).rev()
+            .filter(|len| key.is_char_boundary(*len))
+            .find_map(|len| tree.get_key_value(&key[..len]))
+            .map(|(key, _)| key.as_str());
+        for key in ["cards", "cardboard", "carts", "caree", "carél", "carê", "cab", "c", "b", "", "dogs", "do", "zebra"].iter() {
+            assert_eq!(naive(key), tree.longest_prefix_match(*key).map(|(key, _)| key.as_str()), "{}", key);
+        }
+
+        // 経路表
+        let mut routes: RBTree<Vec<u8>, &str> = RBTree::new();
+        routes.insert(vec![10], "10/8");
+        routes.insert(vec![10, 1], "10.1/16");
+        routes.insert(vec![10, 1, 2], "10.1.2/24");
+        routes.insert(vec![192, 168], "192.168/16");
+        routes.insert(vec![], "default");
+        let route = |address: &[u8]| *routes.longest_prefix_match(address).unwrap().1;
+        assert_eq!("10.1.2/24", route(&[10, 1, 2, 3]));
+        assert_eq!("10.1/16", route(&[10, 1, 3, 3]));
+        assert_eq!("10/8", route(&[10, 2, 2, 3]));
+        assert_eq!("10/8", route(&[10, 0, 255, 255]));
+        assert_eq!("192.168/16", route(&[192, 168, 0, 1]));
+        assert_eq!("default", route(&[192, 167, 0, 1]));
+        assert_eq!("default", route(&[11, 1, 2, 3]));
+        assert_eq!(vec!["10/8", "10.1/16", "10.1.2/24"], routes.prefix(&[10][..]).map(|(_, value)| *value).collect::<Vec<_>>());
+        assert_eq!(5, routes.prefix(&[][..]).count());
+    }
+
+    #[test]
+    fn from_sorted_iter() {
+        for len in 0..40 {
+            let tree: RBTree<usize, usize> = RBTree::from_sorted_iter((0..len).map(|key| (key, key * 2)));
+            assert_eq!(len, tree.len());
+            assert_eq!((0..len).collect::<Vec<_>>(), keys(&tree));
+            assert_rb_tree(&tree);
+        }
+        let tree: RBTree<usize, usize> = RBTree::from_sorted_iter(vec![(1, 10), (2, 20), (3, 30)]);
+        assert_eq!(vec![(&1, &10), (&2, &20), (&3, &30)], tree.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn order_statistics() {
+        let mut tree: RBTree<usize, usize> = RBTree::new();
+        for key in (0..100).map(|key| key * 2) {
+            tree.insert(key, key * 10);
+        }
+        assert_rb_tree(&tree);
+        assert_eq!(Some((&0, &0)), tree.get_by_index(0));
+        assert_eq!(Some((&50, &500)), tree.get_by_index(25));
+        assert_eq!(Some((&198, &1980)), tree.get_by_index(99));
+        assert_eq!(None, tree.get_by_index(100));
+
+        assert_eq!(0, tree.rank(&0));
+        assert_eq!(25, tree.rank(&50));
+        assert_eq!(26, tree.rank(&51));
+        assert_eq!(100, tree.rank(&1000));
+
+        assert_eq!(5, tree.range_count(10..20));
+        assert_eq!(6, tree.range_count(10..=20));
+        assert_eq!(5, tree.range_count(11..=20));
+        assert_eq!(100, tree.range_count(..));
+        assert_eq!(0, tree.range_count((Bound::Included(20), Bound::Excluded(10))));
+
+        assert_eq!(Some((50, 500)), tree.remove_by_index(25));
+        assert_eq!(Some((52, 520)), tree.remove_by_index(25));
+        assert_eq!(None, tree.remove_by_index(98));
+        assert_eq!(98, tree.len());
+        assert_eq!(25, tree.rank(&54));
+        assert_rb_tree(&tree);
+
+        for key in 0..20 {
+            tree.remove(&(key * 6)).unwrap();
+            assert_rb_tree(&tree);
+        }
+        let removed = tree.remove_range(100..150);
+        assert_rb_tree(&tree);
+        assert_rb_tree(&removed);
+        assert_eq!(Some((&100, &1000)), removed.get_by_index(0));
+        assert_eq!(tree.len(), tree.range_count(..));
+    }
+
+    // valueの和を集約する
+    struct Sum;
+
+    impl Augment<usize, usize> for Sum {
+        type Summary = usize;
+        type Update = ();
+
+        fn empty() -> usize { 0 }
+
+        fn combine(left: &usize, node: (&usize, &usize), right: &usize) -> usize {
+            left + node.1 + right
+        }
+    }
+
+    // 順序が崩れていないことを確認するため、keyを昇順に並べる
+    struct Keys;
+
+    impl Augment<usize, usize> for Keys {
+        type Summary = Vec<usize>;
+        type Update = ();
+
+        fn empty() -> Vec<usize> { vec![] }
+
+        fn combine(left: &Vec<usize>, node: (&usize, &usize), right: &Vec<usize>) -> Vec<usize> {
+            left.iter().chain(std::iter::once(node.0)).chain(right.iter()).cloned().collect()
+        }
+    }
+
+    #[test]
+    fn fold_range() {
+        let mut tree: RBTree<usize, usize, Sum> = RBTree::new();
+        let mut keys_tree: RBTree<usize, usize, Keys> = RBTree::new();
+        for key in (0..101).map(|key| key * 37 % 101) {
+            tree.insert(key, key * 2);
+            keys_tree.insert(key, key * 2);
+        }
+        assert_rb_tree(&tree);
+        assert_eq!((0..101).map(|key| key * 2).sum::<usize>(), tree.fold_range(..));
+        assert_eq!((10..20).map(|key| key * 2).sum::<usize>(), tree.fold_range(10..20));
+        assert_eq!((10..=20).map(|key| key * 2).sum::<usize>(), tree.fold_range(10..=20));
+        assert_eq!((51..101).map(|key| key * 2).sum::<usize>(), tree.fold_range((Bound::Excluded(50), Bound::Unbounded)));
+        assert_eq!(0, tree.fold_range(200..300));
+        assert_eq!((30..70).collect::<Vec<_>>(), keys_tree.fold_range(30..70));
+
+        // valueの上書き、削除、範囲の削除の後もsummaryが保たれる
+        tree.insert(10, 1000);
+        for key in (0..101).filter(|key| key % 3 == 0) {
+            tree.remove(&key).unwrap();
+            keys_tree.remove(&key).unwrap();
+        }
+        let removed = tree.remove_range(80..90);
+        keys_tree.remove_range(80..90);
+        assert_rb_tree(&tree);
+        let expected = |start: usize, end: usize| {
+            (start..end).filter(|key| key % 3 != 0 && !(80..90).contains(key))
+                .map(|key| if key == 10 { 1000 } else { key * 2 })
+                .sum::<usize>()
+        };
+        for start in (0..101).step_by(7) {
+            for end in (start..=101).step_by(5) {
+                assert_eq!(expected(start, end), tree.fold_range(start..end));
+            }
+        }
+        assert_eq!((80..90).filter(|key| key % 3 != 0).map(|key| key * 2).sum::<usize>(), removed.fold_range(..));
+        assert_eq!(
+            (0..101).filter(|key| key % 3 != 0 && !(80..90).contains(key)).collect::<Vec<_>>(),
+            keys_tree.fold_range(..)
+        );
+
+        let tree: RBTree<usize, usize, Sum> = RBTree::from_sorted_iter((0..50).map(|key| (key, key)));
+        assert_eq!((20..40).sum::<usize>(), tree.fold_range(20..40));
+    }
+
+    // valueの和を集約し、範囲の全てのvalueに加算する
+    struct AddSum;
+
+    impl Augment<usize, usize> for AddSum {
+        type Summary = usize;
+        type Update = usize;
+
+        fn empty() -> usize { 0 }
+
+        fn combine(left: &usize, node: (&usize, &usize), right: &usize) -> usize {
+            left + node.1 + right
+        }
+
+        fn apply(update: &usize, value: &mut usize) {
+            *value += update;
+        }
+
+        fn apply_summary(update: &usize, summary: &usize, size: usize) -> usize {
+            summary + update * size
+        }
+
+        fn compose(first: &usize, second: &usize) -> usize {
+            first + second
+        }
+
+        const HAS_UPDATE: bool = true;
+    }
+
+    #[test]
+    fn apply_range() {
+        let mut tree: RBTree<usize, usize, AddSum> = RBTree::new();
+        // keyごとのvalue。木に存在しないkeyはNone
+        let mut values: Vec<Option<usize>> = vec![None; 200];
+        for key in (0..199).map(|key| key * 71 % 199) {
+            tree.insert(key, key);
+            values[key] = Some(key);
+        }
+        let mut seed = 12345usize;
+        let mut random = |max: usize| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 33) % max
+        };
+        for step in 0..300 {
+            let start = random(200);
+            let end = start + random(200 - start + 1);
+            match step % 4 {
+                0 | 1 => {
+                    let delta = random(10);
+                    tree.apply_range(start..end, delta);
+                    for value in values[start..end].iter_mut().flatten() { *value += delta; }
+                },
+                2 => {
+                    if values[start].is_some() {
+                        tree.remove(&start).unwrap();
+                        values[start] = None;
+                    } else {
+                        tree.insert(start, 0);
+                        values[start] = Some(0);
+                    }
+                },
+                _ => {
+                    let expected: usize = values[start..end].iter().flatten().sum();
+                    assert_eq!(expected, tree.fold_range(start..end));
+                },
+            }
+        }
+        assert_rb_tree(&tree);
+        let expected: Vec<(usize, usize)> = values.iter().enumerate()
+            .filter_map(|(key, value)| value.map(|value| (key, value)))
+            .collect();
+        assert_eq!(expected, tree.iter().map(|(key, value)| (*key, *value)).collect::<Vec<_>>());
+        assert_eq!(expected.iter().rev().cloned().collect::<Vec<_>>(), tree.iter().rev().map(|(key, value)| (*key, *value)).collect::<Vec<_>>());
+
+        // 溜まっているupdateは切り離した木にも引き継がれる
+        tree.apply_range(.., 1000);
+        let removed = tree.remove_range(50..150);
+        assert_rb_tree(&tree);
+        assert_rb_tree(&removed);
+        let removed_sum: usize = expected.iter().filter(|(key, _)| (50..150).contains(key)).map(|(_, value)| value + 1000).sum();
+        assert_eq!(removed_sum, removed.fold_range(..));
+        assert_eq!(expected.first().map(|(key, value)| (*key, value + 1000)), tree.pop_first());
+    }
+
+    #[test]
+    #[should_panic(expected = "HAS_UPDATE = true")]
+    fn apply_range_without_update() {
+        let mut tree: RBTree<usize, usize> = RBTree::from([(1, 1), (2, 2)]);
+        tree.apply_range(.., ());
+    }
+
+    #[test]
+    fn remove_range_random() {
+        // 切り離した要素はotherに溜めて、ときどきappendで戻す
+        let mut tree: RBTree<usize, usize, AddSum> = RBTree::new();
+        let mut other: RBTree<usize, usize, AddSum> = RBTree::new();
+        let mut map: BTreeMap<usize, usize> = BTreeMap::new();
+        let mut other_map: BTreeMap<usize, usize> = BTreeMap::new();
+        let mut seed = 2024usize;
+        let mut random = |max: usize| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 33) % max
+        };
+        fn bound(kind: usize, key: usize) -> Bound<usize> {
+            match kind {
+                0 => Bound::Included(key),
+                1 => Bound::Excluded(key),
+                _ => Bound::Unbounded,
+            }
+        }
+        for step in 0..1500 {
+            let range = (bound(random(3), random(300)), bound(random(3), random(300)));
+            match random(8) {
+                0 => {
+                    let removed = tree.remove_range(range);
+                    let expected: BTreeMap<usize, usize> = map.iter().filter(|(key, _)| range.contains(key)).map(|(key, value)| (*key, *value)).collect();
+                    map.retain(|key, _| !range.contains(key));
+                    assert_rb_tree(&removed);
+                    assert_eq!(expected.values().sum::<usize>(), removed.fold_range(..));
+                    assert!(expected.iter().eq(removed.iter()));
+                    // keyが重ならないように、otherには入っていないkeyだけを戻す
+                    for (key, value) in removed {
+                        other_map.entry(key).or_insert(value);
+                        if !other.contains_key(&key) { other.insert(key, value); }
+                    }
+                },
+                1 => {
+                    let key = random(300);
+                    let mut split = tree.split_off(&key);
+                    let mut expected = map.split_off(&key);
+                    assert_rb_tree(&split);
+                    assert_eq!(expected.values().sum::<usize>(), split.fold_range(..));
+                    // otherのvalueで上書きされる
+                    split.append(&mut other);
+                    expected.append(&mut other_map);
+                    assert_rb_tree(&split);
+                    assert!(other.is_empty());
+                    other = split;
+                    other_map = expected;
+                },
+                2 => {
+                    tree.append(&mut other);
+                    map.append(&mut other_map);
+                    assert!(other.is_empty());
+                },
+                3 => {
+                    let delta = random(10);
+                    tree.apply_range(range, delta);
+                    for (_, value) in map.iter_mut().filter(|(key, _)| range.contains(key)) { *value += delta; }
+                },
+                _ => {
+                    let key = random(300);
+                    if random(3) == 0 {
+                        assert_eq!(map.remove(&key), tree.remove(&key));
+                    } else {
+                        assert_eq!(map.insert(key, step), tree.insert(key, step));
+                    }
+                },
+            }
+            assert_rb_tree(&tree);
+            assert_rb_tree(&other);
+            let range = (bound(random(3), random(300)), bound(random(3), random(300)));
+            let expected: usize = map.iter().filter(|(key, _)| range.contains(key)).map(|(_, value)| value).sum();
+            assert_eq!(expected, tree.fold_range(range));
+            assert_eq!(other_map.values().sum::<usize>(), other.fold_range(..));
+        }
+        assert!(map.iter().eq(tree.iter()));
+        assert!(other_map.iter().eq(other.iter()));
+    }
+
+    #[test]
+    fn clone() {
+        let mut tree: RBTree<usize, String, ()> = RBTree::new();
+        for key in (0..50).map(|key| key * 7 % 50) {
+            tree.insert(key, key.to_string());
+        }
+        let mut cloned = tree.clone();
+        assert_rb_tree(&cloned);
+        assert!(tree.iter().eq(cloned.iter()));
+
+        // コピーした木を変更しても元の木は変わらない
+        cloned.insert(3, "three".to_string());
+        cloned.remove(&10).unwrap();
+        cloned.insert(100, "100".to_string());
+        assert_rb_tree(&cloned);
+        assert_eq!(Some((&3, &"3".to_string())), tree.get_by_index(3));
+        assert_eq!(Some((&3, &"three".to_string())), cloned.get_by_index(3));
+        assert_eq!((0..50).collect::<Vec<_>>(), keys(&tree));
+        assert_eq!(50, cloned.len());
+        drop(tree);
+        assert_eq!(Some((&100, &"100".to_string())), cloned.last_key_value());
+
+        // 溜まっているupdateもコピーされる
+        let mut tree: RBTree<usize, usize, AddSum> = RBTree::new();
+        for key in 0..30 {
+            tree.insert(key, key);
+        }
+        tree.apply_range(10..20, 100);
+        let cloned = tree.clone();
+        tree.apply_range(.., 1);
+        assert_eq!((0..30).sum::<usize>() + 1000, cloned.fold_range(..));
+        assert_eq!(Some((&15, &115)), cloned.get_by_index(15));
+        assert_eq!(Some((&15, &116)), tree.get_by_index(15));
+    }
+
+    // ASCIIの大文字小文字を区別しない順序。strでもStringでも比較できる
+    #[derive(Clone)]
+    struct AsciiCaseInsensitive;
+
+    impl Compare<str> for AsciiCaseInsensitive {
+        fn compare(&self, a: &str, b: &str) -> Ordering {
+            a.bytes().map(|c| c.to_ascii_lowercase()).cmp(b.bytes().map(|c| c.to_ascii_lowercase()))
+        }
+    }
+
+    impl Compare<String> for AsciiCaseInsensitive {
+        fn compare(&self, a: &String, b: &String) -> Ordering {
+            Compare::<str>::compare(self, a, b)
+        }
+    }
+
+    #[test]
+    #[allow(clippy::reversed_empty_ranges)]
+    fn comparator() {
+        // 同じとみなすkeyをinsertした場合は、keyは最初のものが残ってvalueだけ上書きされる
+        let mut tree: RBTree<String, usize, (), _> = RBTree::with_comparator(AsciiCaseInsensitive);
+        for (value, key) in ["banana", "Apple", "cherry", "BANANA", "apple", "Date"].iter().enumerate() {
+            tree.insert(key.to_string(), value);
+        }
+        assert_rb_tree(&tree);
+        assert_eq!(vec![("Apple", 4), ("banana", 3), ("cherry", 2), ("Date", 5)], tree.iter().map(|(key, value)| (key.as_str(), *value)).collect::<Vec<_>>());
+        assert_eq!(2, tree.rank("CHERRY"));
+        assert_eq!(vec!["banana", "cherry"], tree.range::<str, _>((Bound::Included("B"), Bound::Excluded("d"))).map(|(key, _)| key.as_str()).collect::<Vec<_>>());
+        assert!(tree.search("DATE").is_some());
+        assert_eq!(Some(4), tree.remove("APPLE"));
+        assert_eq!(None, tree.remove("apple"));
+        assert_rb_tree(&tree);
+        assert_eq!(3, tree.len());
+
+        // 木どうしの比較でも、keyはKのOrdではなく木の順序で比べる
+        let mut upper: RBTree<String, usize, (), _> = RBTree::with_comparator(AsciiCaseInsensitive);
+        upper.extend(vec![("DATE".to_string(), 5), ("BANANA".to_string(), 3), ("CHERRY".to_string(), 2)]);
+        assert_eq!(tree, upper);
+        upper.insert("Cherry".to_string(), 4);
+        assert!(tree < upper);
+        upper.insert("Apple".to_string(), 0);
+        assert!(tree > upper);
+
+        // Ordを実装していないkeyでも、順序を渡せば使える
+        let mut floats: RBTree<f64, usize, (), _> = RBTree::with_comparator(|a: &f64, b: &f64| a.total_cmp(b));
+        for (value, key) in [0.5, -1.0, 2.5, 0.0, -0.0].iter().enumerate() {
+            floats.insert(*key, value);
+        }
+        assert_rb_tree(&floats);
+        assert_eq!(vec![-1.0, -0.0, 0.0, 0.5, 2.5], keys(&floats));
+        assert_eq!(Some(&2), floats.get(&2.5));
+        assert_eq!(Some(0), floats.remove(&0.5));
+        assert_eq!(3, floats.rank(&0.1));
+
+        // クロージャで降順に並べる。範囲も木の順序で指定する
+        let mut tree: RBTree<usize, usize, AddSum, _> = RBTree::with_comparator(|a: &usize, b: &usize| b.cmp(a));
+        for key in (0..100).map(|key| key * 37 % 100) {
+            tree.insert(key, key);
+        }
+        assert_rb_tree(&tree);
+        assert_eq!((0..100).rev().collect::<Vec<_>>(), keys(&tree));
+        assert_eq!(Some((&99, &99)), tree.first_key_value());
+        assert_eq!(9, tree.rank(&90));
+        assert_eq!(21, tree.range_count(60..=40));
+        assert_eq!((40..=60).sum::<usize>(), tree.fold_range(60..=40));
+        assert_eq!((41..=60).rev().collect::<Vec<_>>(), tree.range(60..40).map(|(key, _)| *key).collect::<Vec<_>>());
+        assert_eq!(0, tree.range(40..60).count());
+        tree.apply_range(..=90, 1000);
+        assert_eq!(Some((&90, &1090)), tree.get_by_index(9));
+        assert_eq!(Some((&89, &89)), tree.get_by_index(10));
+
+        // 切り離した木も同じ順序を持つ
+        let mut removed = tree.remove_range(60..=40);
+        assert_rb_tree(&removed);
+        assert_eq!((40..=60).rev().collect::<Vec<_>>(), keys(&removed));
+        removed.insert(50, 50);
+        removed.insert(100, 100);
+        assert_eq!(Some((&100, &100)), removed.first_key_value());
+        tree.shrink_to_fit();
+        assert_rb_tree(&tree);
+        assert_eq!((0..40).chain(61..100).rev().collect::<Vec<_>>(), keys(&tree));
+    }
+
+    #[test]
+    fn std_traits() {
+        let tree: RBTree<usize, &str> = RBTree::from([(3, "c"), (1, "a"), (2, "b")]);
+        assert_rb_tree(&tree);
+        assert_eq!("{1: \"a\", 2: \"b\", 3: \"c\"}", format!("{:?}", tree));
+        assert_eq!("{}", format!("{:?}", RBTree::<usize, usize>::default()));
+        assert_eq!("b", tree[&2]);
+
+        // 後から同じkeyを入れた場合は上書きされる
+        let mut collected: RBTree<usize, &str> = vec![(2, "x"), (1, "a"), (3, "c")].into_iter().collect();
+        assert_ne!(tree, collected);
+        assert!(collected > tree);
+        collected.extend(vec![(2, "b")]);
+        assert_eq!(tree, collected);
+        assert_eq!(tree.partial_cmp(&collected), Some(Ordering::Equal));
+        let hash = |tree: &RBTree<usize, &str>| {
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            tree.hash(&mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(hash(&tree), hash(&collected));
+
+        let mut extended: RBTree<usize, usize> = RBTree::new();
+        extended.extend(&[(5, 50), (4, 40)].iter().cloned().collect::<RBTree<usize, usize>>());
+        extended.extend(vec![(6, 60)]);
+        assert_rb_tree(&extended);
+        assert_eq!(vec![(&4, &40), (&5, &50), (&6, &60)], extended.iter().collect::<Vec<_>>());
+        // 同じ要素を持つ木は、挿入の順序や木の形に関係なく等しい
+        let reversed: RBTree<usize, usize> = (4..7).rev().map(|key| (key, key * 10)).collect();
+        assert_eq!(extended, reversed);
+        assert_eq!(Ordering::Less, RBTree::<usize, usize>::from([(1, 1)]).cmp(&RBTree::<usize, usize>::from([(1, 2)])));
+        assert_eq!(Ordering::Greater, RBTree::<usize, usize>::from([(1, 1), (2, 2)]).cmp(&RBTree::<usize, usize>::from([(1, 1)])));
+    }
+
+    #[test]
+    #[should_panic(expected = "no entry found for key")]
+    fn index_missing_key() {
+        let tree: RBTree<usize, usize> = RBTree::from([(1, 1)]);
+        let _ = tree[&2];
+    }
+
+    #[test]
+    fn exact_size_iter() {
+        let mut tree: RBTree<usize, usize> = (0..100).map(|key| (key * 2, key)).collect();
+        assert_eq!(100, tree.iter().len());
+        assert_eq!(100, tree.keys().len());
+        assert_eq!(0, tree.range(11..12).len());
+
+        // 前後から取り出すたびに残りの数が減り、出会った後はNoneを返し続ける
+        let mut iter = tree.range(10..=30);
+        assert_eq!((11, Some(11)), iter.size_hint());
+        for len in (0..11).rev() {
+            if len % 2 == 0 { iter.next() } else { iter.next_back() }.unwrap();
+            assert_eq!(len, iter.len());
+        }
+        assert_eq!(None, iter.next());
+        assert_eq!(None, iter.next_back());
+
+        let mut iter = tree.range_mut(..20);
+        assert_eq!(10, iter.len());
+        iter.next_back();
+        assert_eq!(9, iter.count());
+
+        let mut iter = tree.into_iter();
+        assert_eq!(100, iter.len());
+        assert_eq!(Some((0, 0)), iter.next());
+        assert_eq!(Some((198, 99)), iter.next_back());
+        assert_eq!(98, iter.len());
+        assert!(iter.map(|(key, _)| key).eq((1..99).map(|key| key * 2)));
+    }
+
+    // BTreeMapとRBTreeに同じ操作をするための共通のtrait
+    // 実装は同じマクロから生成するので、両方の型が同じ名前と引数のメソッドを持っていることも確かめられる
+    trait SortedMap: Default + Debug + PartialEq + Extend<(usize, usize)> + FromIterator<(usize, usize)> + IntoIterator<Item = (usize, usize)> {
+        fn insert(&mut self, key: usize, value: usize) -> Option<usize>;
+        fn remove(&mut self, key: &usize) -> Option<usize>;
+        fn remove_entry(&mut self, key: &usize) -> Option<(usize, usize)>;
+        fn get(&self, key: &usize) -> Option<&usize>;
+        fn get_key_value(&self, key: &usize) -> Option<(&usize, &usize)>;
+        fn get_mut(&mut self, key: &usize) -> Option<&mut usize>;
+        fn contains_key(&self, key: &usize) -> bool;
+        fn index(&self, key: &usize) -> usize;
+        fn len(&self) -> usize;
+        fn is_empty(&self) -> bool;
+        fn clear(&mut self);
+        fn first_key_value(&self) -> Option<(&usize, &usize)>;
+        fn last_key_value(&self) -> Option<(&usize, &usize)>;
+        fn pop_first(&mut self) -> Option<(usize, usize)>;
+        fn pop_last(&mut self) -> Option<(usize, usize)>;
+        fn count(&mut self, key: usize) -> usize;
+        fn range(&self, start: Bound<usize>, end: Bound<usize>) -> Vec<(usize, usize)>;
+        fn range_mut(&mut self, start: Bound<usize>, end: Bound<usize>, delta: usize);
+        fn iter_rev(&self) -> Vec<(usize, usize)>;
+        fn iter_mut(&mut self, delta: usize);
+        fn keys(&self) -> Vec<usize>;
+        fn values(&self) -> Vec<usize>;
+        fn values_mut(&mut self, delta: usize);
+        fn split_off(&mut self, key: &usize) -> Self;
+        fn append(&mut self, other: &mut Self);
+        fn retain(&mut self, modulo: usize);
+        fn into_keys(self) -> Vec<usize>;
+        fn into_values(self) -> Vec<usize>;
+    }
+
+    macro_rules! impl_sorted_map {
+        ($map:ty) => {
+            impl SortedMap for $map {
+                fn insert(&mut self, key: usize, value: usize) -> Option<usize> { self.insert(key, value) }
+                fn remove(&mut self, key: &usize) -> Option<usize> { self.remove(key) }
+                fn remove_entry(&mut self, key: &usize) -> Option<(usize, usize)> { self.remove_entry(key) }
+                fn get(&self, key: &usize) -> Option<&usize> { self.get(key) }
+                fn get_key_value(&self, key: &usize) -> Option<(&usize, &usize)> { self.get_key_value(key) }
+                fn get_mut(&mut self, key: &usize) -> Option<&mut usize> { self.get_mut(key) }
+                fn contains_key(&self, key: &usize) -> bool { self.contains_key(key) }
+                fn index(&self, key: &usize) -> usize { self[key] }
+                fn len(&self) -> usize { self.len() }
+                fn is_empty(&self) -> bool { self.is_empty() }
+                fn clear(&mut self) { self.clear() }
+                fn first_key_value(&self) -> Option<(&usize, &usize)> { self.first_key_value() }
+                fn last_key_value(&self) -> Option<(&usize, &usize)> { self.last_key_value() }
+                fn pop_first(&mut self) -> Option<(usize, usize)> { self.pop_first() }
+                fn pop_last(&mut self) -> Option<(usize, usize)> { self.pop_last() }
+                fn count(&mut self, key: usize) -> usize {
+                    let count = self.entry(key).and_modify(|count| *count += 1).or_insert(1);
+                    *count
+                }
+                fn range(&self, start: Bound<usize>, end: Bound<usize>) -> Vec<(usize, usize)> {
+                    self.range((start, end)).map(|(key, value)| (*key, *value)).collect()
+                }
+                fn range_mut(&mut self, start: Bound<usize>, end: Bound<usize>, delta: usize) {
+                    for (_, value) in self.range_mut((start, end)) { *value += delta; }
+                }
+                fn iter_rev(&self) -> Vec<(usize, usize)> { self.iter().rev().map(|(key, value)| (*key, *value)).collect() }
+                fn iter_mut(&mut self, delta: usize) {
+                    for (key, value) in self.iter_mut() { *value += key * delta; }
+                }
+                fn keys(&self) -> Vec<usize> { self.keys().cloned().collect() }
+                fn values(&self) -> Vec<usize> { self.values().cloned().collect() }
+                fn values_mut(&mut self, delta: usize) {
+                    for value in self.values_mut() { *value += delta; }
+                }
+                fn split_off(&mut self, key: &usize) -> $map { self.split_off(key) }
+                fn append(&mut self, other: &mut $map) { self.append(other) }
+                fn retain(&mut self, modulo: usize) { self.retain(|key, value| { *value += 1; key % modulo != 0 }) }
+                fn into_keys(self) -> Vec<usize> { self.into_keys().collect() }
+                fn into_values(self) -> Vec<usize> { self.into_values().rev().collect() }
+            }
+        };
+    }
+
+    impl_sorted_map!(BTreeMap<usize, usize>);
+    impl_sorted_map!(RBTree<usize, usize>);
+
+    // 操作と、その結果を全て記録する
+    fn sorted_map_scenario<M: SortedMap>(seed: usize) -> Vec<String> {
+        let mut log = vec![];
+        let mut seed = seed;
+        let mut random = |max: usize| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 33) % max
+        };
+        let mut map: M = (0..20).map(|key| (key * 7 % 20, key)).collect();
+        log.push(format!("{:?}", map));
+        for _ in 0..400 {
+            let key = random(60);
+            let result = match random(22) {
+                0..=2 => format!("insert {:?}", map.insert(key, random(1000))),
+                3 | 4 => format!("remove {:?}", map.remove(&key)),
+                5 => format!("remove_entry {:?}", map.remove_entry(&key)),
+                6 => format!("get {:?} {:?}", map.get(&key), map.get_key_value(&key)),
+                7 => format!("get_mut {:?}", map.get_mut(&key).map(|value| { *value += 1; *value })),
+                8 => format!("contains_key {:?} {:?}", map.contains_key(&key), map.contains_key(&key).then(|| map.index(&key))),
+                9 => format!("first_last {:?} {:?}", map.first_key_value(), map.last_key_value()),
+                10 => format!("pop {:?} {:?}", map.pop_first(), map.pop_last()),
+                11 | 12 => format!("count {:?}", map.count(key)),
+                13 => {
+                    let start = [Bound::Included(key), Bound::Excluded(key), Bound::Unbounded][random(3)];
+                    let end = [Bound::Included(key + 10), Bound::Excluded(key + 10), Bound::Unbounded][random(3)];
+                    map.range_mut(start, end, 1);
+                    format!("range {:?}", map.range(start, end))
+                },
+                14 => {
+                    map.iter_mut(random(3));
+                    map.values_mut(1);
+                    format!("iter {:?} {:?} {:?}", map.iter_rev(), map.keys(), map.values())
+                },
+                15 => {
+                    let mut other = map.split_off(&key);
+                    let result = format!("split_off {:?} {:?}", map, other);
+                    other.extend((0..5).map(|value| (key + value * 3, value)));
+                    map.append(&mut other);
+                    format!("{} {:?} {:?}", result, other.is_empty(), other.len())
+                },
+                16 => {
+                    let mut other: M = (0..random(20)).map(|value| (random(60), value)).collect();
+                    other.append(&mut map);
+                    map = other;
+                    format!("append {:?}", map)
+                },
+                17 => {
+                    map.retain(random(5) + 2);
+                    format!("retain {:?}", map)
+                },
+                18 => {
+                    let cloned: M = map.range(Bound::Unbounded, Bound::Unbounded).into_iter().collect();
+                    format!("into {:?} {:?} {:?}", cloned == map, M::from_iter(map.range(Bound::Unbounded, Bound::Unbounded)).into_keys(), M::from_iter(map.range(Bound::Unbounded, Bound::Unbounded)).into_values())
+                },
+                19 if random(10) == 0 => {
+                    map.clear();
+                    format!("clear {:?} {:?}", map.is_empty(), map)
+                },
+                _ => format!("len {:?} {:?}", map.len(), map.is_empty()),
+            };
+            log.push(format!("{} {}", key, result));
+        }
+        log.push(format!("{:?}", map.into_iter().collect::<Vec<_>>()));
+        log
+    }
+
+    #[test]
+    fn btree_map_compatible() {
+        for seed in 0..20 {
+            assert_eq!(sorted_map_scenario::<BTreeMap<usize, usize>>(seed), sorted_map_scenario::<RBTree<usize, usize>>(seed));
+        }
+    }
+
+    #[test]
+    fn btree_map_conversion() {
+        let map: BTreeMap<usize, usize> = (0..100).map(|key| (key * 3, key)).collect();
+        let tree = RBTree::<usize, usize>::from(map.clone());
+        assert_rb_tree(&tree);
+        assert!(map.iter().eq(tree.iter()));
+        let converted: BTreeMap<usize, usize> = tree.into();
+        assert_eq!(map, converted);
+
+        // KのOrdと違う順序の木からは、BTreeMapの順序に並べ直される
+        let mut tree: RBTree<usize, usize, (), _> = RBTree::with_comparator(|a: &usize, b: &usize| b.cmp(a));
+        tree.extend(map.clone());
+        assert_eq!(Some((&297, &99)), tree.first_key_value());
+        assert_eq!(map, BTreeMap::from(tree));
+
+        // summaryも組み立てる時に計算される
+        let tree = RBTree::<usize, usize, AddSum>::from(map);
+        assert_rb_tree(&tree);
+        assert_eq!((0..100).sum::<usize>(), tree.fold_range(..));
+    }
+
+    #[test]
+    fn non_clone_key() {
+        // BTreeMapと同じく、CloneもDebugも実装していないkeyでも使える
+        #[derive(PartialEq, Eq, PartialOrd, Ord)]
+        struct Key(usize);
+
+        let mut tree: RBTree<Key, usize> = RBTree::new();
+        for key in 0..100 {
+            assert_eq!(None, tree.insert(Key(key), key));
+        }
+        *tree.entry(Key(100)).or_insert(0) += 100;
+        assert_eq!(Some(&50), tree.get(&Key(50)));
+        assert_eq!(Some(50), tree.remove(&Key(50)));
+        tree.retain(|key, _| key.0 % 2 == 0);
+        let mut right = tree.split_off(&Key(60));
+        assert_eq!(29, tree.len());
+        right.remove_range(Key(80)..Key(90));
+        tree.append(&mut right);
+        assert_rb_tree(&tree);
+        assert_eq!(45, tree.len());
+        assert!(tree.into_iter().map(|(key, value)| (key.0, value)).eq((0..101).filter(|key| key % 2 == 0 && !(80..90).contains(key) && *key != 50).map(|key| (key, key))));
+    }
+
+    #[test]
+    fn node_handle() {
+        let mut tree: RBTree<usize, usize> = RBTree::new();
+        let mut handles = vec![];
+        for key in (0..100).map(|key| key * 37 % 100) {
+            let (handle, old_value) = tree.insert_with_handle(key, key);
+            assert_eq!(None, old_value);
+            handles.push((key, handle));
+        }
+        // 回転で木の形が変わっても同じ要素を指す
+        for (key, handle) in &handles {
+            assert_eq!(Some((key, key)), tree.get_by_handle(*handle));
+        }
+        // 上書きした場合は同じ要素を指すNodeHandleを返す
+        let (handle, old_value) = tree.insert_with_handle(37, 1000);
+        assert_eq!((37, Some(37)), (handles[1].0, old_value));
+        assert_eq!(handles[1].1, handle);
+        assert_eq!(Some(handle), tree.handle_of(&37));
+        *tree.get_mut_by_handle(handle).unwrap() = 37;
+
+        // 順に辿る
+        let mut handle = tree.handle_of(&0);
+        let mut keys = vec![];
+        while let Some(current) = handle {
+            keys.push(*tree.get_by_handle(current).unwrap().0);
+            handle = tree.next_handle(current);
+        }
+        assert_eq!((0..100).collect::<Vec<_>>(), keys);
+        let last = tree.handle_of(&99).unwrap();
+        assert_eq!(Some((&98, &98)), tree.prev_handle(last).and_then(|prev| tree.get_by_handle(prev)));
+
+        // 取り除いた要素を指すNodeHandleは、同じ枠に別の要素が置かれても使えない
+        for (key, handle) in handles.iter().filter(|(key, _)| key % 2 == 0) {
+            assert_eq!(Some((*key, *key)), tree.remove_by_handle(*handle));
+            assert_eq!(None, tree.remove_by_handle(*handle));
+        }
+        assert_rb_tree(&tree);
+        for key in 100..150 {
+            tree.insert(key, key);
+        }
+        assert_eq!(100, tree.nodes.len());
+        for (key, handle) in &handles {
+            let expected = if key % 2 == 0 { None } else { Some((key, key)) };
+            assert_eq!(expected, tree.get_by_handle(*handle));
+            assert_eq!(expected.is_some(), tree.next_handle(*handle).is_some());
+        }
+
+        // 詰めてノードの位置が変わった場合は全て使えなくなる
+        tree.remove(&120);
+        let handle = tree.handle_of(&121).unwrap();
+        tree.shrink_to_fit();
+        assert!(handles.iter().all(|(_, handle)| tree.get_by_handle(*handle).is_none()));
+        assert_eq!(None, tree.get_by_handle(handle));
+        let (handle, _) = tree.insert_with_handle(200, 200);
+        assert_eq!(Some((&200, &200)), tree.get_by_handle(handle));
+        tree.clear();
+        tree.insert(0, 0);
+        assert_eq!(None, tree.get_by_handle(handle));
+
+        // 溜まっているupdateを適用した値を返す
+        let mut tree: RBTree<usize, usize, AddSum> = RBTree::new();
+        let handles: Vec<_> = (0..50).map(|key| tree.insert_with_handle(key, key).0).collect();
+        tree.apply_range(10..20, 100);
+        assert_eq!(Some((&15, &115)), tree.get_by_handle(handles[15]));
+        assert_eq!(Some((15, 115)), tree.remove_by_handle(handles[15]));
+        assert_eq!((0..50).sum::<usize>() + 1000 - 115, tree.fold_range(..));
+        // retainで書き換えたvalueもsummaryに反映される
+        tree.retain(|key, value| {
+            *value += 1;
+            key % 2 == 0
+        });
+        assert_rb_tree(&tree);
+        assert_eq!(Some((&16, &117)), tree.get_by_handle(handles[16]));
+        assert_eq!(tree.values().sum::<usize>(), tree.fold_range(..));
+
+        // retainとappendは残った要素のノードを動かさないので、NodeHandleはそのまま使える
+        let mut tree: RBTree<usize, usize> = RBTree::new();
+        let handles: Vec<_> = (0..100).map(|key| tree.insert_with_handle(key, key).0).collect();
+        tree.retain(|key, value| {
+            *value += 1;
+            key % 3 != 0
+        });
+        // 取り除いた要素の枠には同じkeyが入り直すかもしれないが、世代が違うので古いNodeHandleは使えない
+        let mut other: RBTree<usize, usize> = (50..150).map(|key| (key, key * 10)).collect();
+        tree.append(&mut other);
+        assert_rb_tree(&tree);
+        assert!(other.is_empty());
+        assert_eq!(133, tree.len());
+        for (key, handle) in handles.iter().enumerate() {
+            let expected = match key {
+                _ if key % 3 == 0 => None,
+                50.. => Some(key * 10),
+                _ => Some(key + 1),
+            };
+            assert_eq!(expected, tree.get_by_handle(*handle).map(|(_, value)| *value));
+        }
+    }
+
+    #[test]
+    fn slab() {
+        let mut tree: RBTree<usize, String> = RBTree::with_capacity(100);
+        assert!(tree.capacity() >= 100);
+        for key in 0..100 {
+            tree.insert(key, key.to_string());
+        }
+        assert_eq!(100, tree.nodes.len());
+
+        // 削除で空いた枠は次のinsertで使い回す
+        for key in (0..100).step_by(2) {
+            tree.remove(&key).unwrap();
+        }
+        for key in 100..150 {
+            tree.insert(key, key.to_string());
+        }
+        assert_eq!(100, tree.nodes.len());
+        assert_rb_tree(&tree);
+
+        // 切り離したノードは新しい木のslabに移る
+        let removed = tree.remove_range(..50);
+        assert_eq!(25, removed.nodes.len());
+        assert_rb_tree(&removed);
+        assert_rb_tree(&tree);
+
+        tree.shrink_to_fit();
+        assert_eq!(75, tree.nodes.len());
+        assert!(tree.capacity() < 100);
+        assert_rb_tree(&tree);
+        assert_eq!((51..100).step_by(2).chain(100..150).collect::<Vec<_>>(), keys(&tree));
+        assert_eq!(Some((&149, &"149".to_string())), tree.last_key_value());
+
+        // ノードは添字で繋がっているので、木ごと別のスレッドに渡せる
+        let handle = std::thread::spawn(move || {
+            tree.insert(150, "150".to_string());
+            tree.len()
+        });
+        assert_eq!(76, handle.join().unwrap());
+    }
+
+    fn assert_send<T: Send>() {}
+    fn assert_sync<T: Sync>() {}
+
+    #[test]
+    fn send_sync() {
+        // SendとSyncにならない場合はsrc/compile_fail.rsのdoctestで確かめる
+        assert_send::<RBTree<String, Vec<usize>>>();
+        assert_sync::<RBTree<String, Vec<usize>>>();
+        assert_sync::<RBTree<usize, usize, (), fn(&usize, &usize) -> Ordering>>();
+        assert_send::<RBTree<usize, usize, AddSum>>();
+        assert_sync::<set::RBSet<usize>>();
+        assert_send::<cow::CowRBTree<usize, usize>>();
+        assert_sync::<cow::CowRBTree<usize, usize>>();
+        assert_sync::<fixed::StaticRBTree<usize, usize, 4>>();
+        assert_sync::<interval::IntervalTree<usize, String>>();
+
+        // Arc<RwLock<_>>に入れて、複数のスレッドから同時に読む
+        let tree: RBTree<usize, String> = (0..1000).map(|key| (key, key.to_string())).collect();
+        let tree = std::sync::Arc::new(std::sync::RwLock::new(tree));
+        let readers = (0..4).map(|thread| {
+            let tree = std::sync::Arc::clone(&tree);
+            std::thread::spawn(move || {
+                let tree = tree.read().unwrap();
+                (thread..1000).step_by(4).filter(|key| tree.get(key) == Some(&key.to_string())).count()
+            })
+        }).collect::<Vec<_>>();
+        assert_eq!(1000, readers.into_iter().map(|reader| reader.join().unwrap()).sum::<usize>());
+        tree.write().unwrap().insert(1000, "1000".to_string());
+        assert_eq!(1001, tree.read().unwrap().len());
+
+        // updateを溜める木もSendなので、別のスレッドで範囲に加算できる
+        let mut tree: RBTree<usize, usize, AddSum> = RBTree::from_sorted_iter((0..100).map(|key| (key, key)));
+        let handle = std::thread::spawn(move || {
+            tree.apply_range(10..20, 1);
+            tree.fold_range(..)
+        });
+        assert_eq!((0..100).sum::<usize>() + 10, handle.join().unwrap());
+    }
+
+    #[test]
+    fn node_size() {
+        // 親と色を4バイトにまとめるので、keyとvalueの他は親子のリンクとノード数、溜まっているupdateだけになる
+        assert_eq!(4, mem::size_of::<ParentColor>());
+        assert_eq!(4, mem::size_of::<Option<NodeId>>());
+        assert_eq!(28, mem::size_of::<RBNode<u32, u32>>());
+        assert_eq!(40, mem::size_of::<RBNode<u64, u64>>());
+        // ParentColorは0にならないので、空いている枠との区別にも場所を取らない
+        assert_eq!(mem::size_of::<RBNode<u32, u32>>(), mem::size_of::<Slot<u32, u32>>());
+        assert_eq!(mem::size_of::<RBNode<u64, u64>>(), mem::size_of::<Slot<u64, u64>>());
+
+        let id = NodeId::new(12345);
+        for (parent, color) in [(Some(id), Color::Red), (Some(id), Color::Black), (None, Color::Red), (None, Color::Black)] {
+            let parent_color = ParentColor::new(parent, color);
+            assert_eq!(parent, parent_color.parent());
+            assert_eq!(color, parent_color.color());
+        }
+        let last = NodeId::new(ParentColor::NO_PARENT as usize - 2);
+        assert_eq!(Some(last), ParentColor::new(Some(last), Color::Red).parent());
+    }
+
+    // 親と色を4バイトにまとめる前は、親のOption<NodeId>と色を別に持っていたのでRBTree<u32, u32>のノードは32バイトだった
+    // u64のkeyとvalueでは、どちらも8バイトに揃えるので40バイトのまま変わらない
+    const _: () = assert!(mem::size_of::<Slot<u32, u32>>() == 28);
+
+    // 1000万要素の木のslabの大きさを測る。時間がかかるので、以下のように明示的に実行する
+    // cargo test --release memory_footprint -- --ignored --nocapture
+    // 親と色をまとめる前は32バイト/ノードで305MiB、まとめた後は28バイト/ノードで267MiBだった
+    #[test]
+    #[ignore]
+    fn memory_footprint() {
+        const LEN: u32 = 10_000_000;
+        let start = std::time::Instant::now();
+        let mut tree: RBTree<u32, u32> = RBTree::with_capacity(LEN as usize);
+        // 奇数を掛けるとu32の中で重複しないので、ばらばらの順にLEN個のkeyを挿入できる
+        for key in 0..LEN {
+            tree.insert(key.wrapping_mul(2654435761), key);
+        }
+        assert_eq!(LEN as usize, tree.len());
+        let node_size = mem::size_of::<Slot<u32, u32>>();
+        let bytes = tree.capacity() * node_size;
+        println!("RBTree<u32, u32>: {} entries, {} bytes per node, {} MiB in total, built in {:?}", tree.len(), node_size, bytes >> 20, start.elapsed());
+        assert_eq!(LEN as usize * node_size, bytes);
+    }
+
+    fn keys<K: Clone, V, A: Augment<K, V>, C: Compare<K>>(tree: &RBTree<K, V, A, C>) -> Vec<K> {
+        let mut keys = vec![];
+        let mut node = tree.root_node().map(|root| root.min_node());
+        while let Some(current_node) = node {
+            keys.push(current_node.key.clone());
+            node = current_node.next();
+        }
+        keys
+    }
+
+    // 赤黒木の性質(根が黒、赤が連続しない、黒高さが等しい)と親子のリンク、部分木のノード数、lenを検証する
+    // 空いている枠の数とslabに置かれたノードの数が合っていることも確かめる
+    fn assert_rb_tree<K, V, A: Augment<K, V>, C: Compare<K>>(tree: &RBTree<K, V, A, C>) {
+        fn black_height<K, V, A: Augment<K, V>, C: Compare<K>>(tree: &RBTree<K, V, A, C>, node: Option<NodeId>, parent: Option<NodeId>, len: &mut usize) -> usize {
+            let (id, node) = match node {
+                Some(id) => (id, tree.node(id)),
+                None => return 0,
+            };
+            assert_eq!(parent, node.parent());
+            if node.is_red() {
+                assert!(tree.is_black(node.left));
+                assert!(tree.is_black(node.right));
+            }
+            if let Some(left) = node.left { assert_eq!(Ordering::Less, tree.cmp.compare(&tree.node(left).key, &node.key)); }
+            if let Some(right) = node.right { assert_eq!(Ordering::Less, tree.cmp.compare(&node.key, &tree.node(right).key)); }
+            let before_len = *len;
+            *len += 1;
+            let left_height = black_height(tree, node.left, Some(id), len);
+            let right_height = black_height(tree, node.right, Some(id), len);
+            assert_eq!(left_height, right_height);
+            assert_eq!(*len - before_len, node.size as usize);
+            left_height + if node.is_black() { 1 } else { 0 }
+        }
+        if let Some(root) = tree.root {
+            assert!(tree.node(root).is_black());
+        }
+        let mut len = 0;
+        black_height(tree, tree.root, None, &mut len);
+        assert_eq!(tree.len(), len);
+        let mut free = 0;
+        let mut slot = tree.free;
+        while let Some(id) = slot {
+            free += 1;
+            slot = match tree.nodes[id.index()] {
+                Slot::Vacant(next) => next,
+                Slot::Occupied(_) => panic!("free list points to an occupied slot"),
+            };
+        }
+        assert_eq!(tree.nodes.len(), len + free);
+    }
+}
@@ -0,0 +1,299 @@
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::fmt::Debug;
+use core::ops::{Bound, RangeBounds};
+
+use super::{Iter, NodeHandle, RBTree};
+
+// 同じkeyを別々のノードとして持つRBTree
+// 同じkeyのノードは挿入した順に並ぶ
+pub struct RBMultiMap<K: Ord + Clone + Debug, V> {
+    tree: RBTree<K, V>,
+}
+
+impl<K: Ord + Clone + Debug, V> RBMultiMap<K, V> {
+    pub fn new() -> RBMultiMap<K, V> {
+        RBMultiMap {
+            tree: RBTree::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.len() == 0
+    }
+
+    pub fn insert(&mut self, key: K, value: V) {
+        self.tree.insert_multi(key, value);
+    }
+
+    // 挿入した要素を指すNodeHandleを返す。同じkeyの要素がいくつあっても、keyで探さずに取り除ける
+    pub fn insert_with_handle(&mut self, key: K, value: V) -> NodeHandle {
+        let node = self.tree.insert_multi(key, value);
+        self.tree.handle(node)
+    }
+
+    pub fn get_by_handle(&self, handle: NodeHandle) -> Option<(&K, &V)> {
+        self.tree.get_by_handle(handle)
+    }
+
+    pub fn remove_by_handle(&mut self, handle: NodeHandle) -> Option<(K, V)> {
+        self.tree.remove_by_handle(handle)
+    }
+
+    // keyを持つ全ての要素を挿入した順に返す
+    pub fn get_all<Q>(&self, key: &Q) -> Iter<'_, K, V>
+        where K: Borrow<Q>, Q: Ord + ?Sized {
+        self.tree.range((Bound::Included(key), Bound::Included(key)))
+    }
+
+    // key以下の要素の数とkey未満の要素の数の差なので、同じkeyの要素がいくつあってもO(log n)で数えられる
+    pub fn count<Q>(&self, key: &Q) -> usize
+        where K: Borrow<Q>, Q: Ord + ?Sized {
+        self.tree.range_count((Bound::Included(key), Bound::Included(key)))
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+        where K: Borrow<Q>, Q: Ord + ?Sized {
+        self.tree.search(key).is_some()
+    }
+
+    // keyを持つ要素のうち、最初に挿入したものを取り除く
+    pub fn remove_one<Q>(&mut self, key: &Q) -> Option<V>
+        where K: Borrow<Q>, Q: Ord + ?Sized {
+        let node = self.tree.lower_bound(Bound::Included(key))?;
+        if self.tree.node(node).key.borrow() != key { return None; }
+        Some(self.tree.remove_node(node).1)
+    }
+
+    // keyを持つ要素を全て取り除いて、挿入した順に返す
+    pub fn remove_all<Q>(&mut self, key: &Q) -> Vec<V>
+        where K: Borrow<Q>, Q: Ord + ?Sized {
+        self.tree.remove_range((Bound::Included(key), Bound::Included(key)))
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect()
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        self.tree.iter()
+    }
+
+    pub fn range<Q, R>(&self, range: R) -> Iter<'_, K, V>
+        where K: Borrow<Q>, Q: Ord + ?Sized, R: RangeBounds<Q> {
+        self.tree.range(range)
+    }
+}
+
+impl<K: Ord + Clone + Debug, V> Default for RBMultiMap<K, V> {
+    fn default() -> RBMultiMap<K, V> {
+        RBMultiMap::new()
+    }
+}
+
+impl<'a, K: Ord + Clone + Debug, V> IntoIterator for &'a RBMultiMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+// 同じ要素を個数として持つ集合
+// 同じ要素はまとめて1つのノードに個数を持たせる
+pub struct RBMultiSet<T: Ord + Clone + Debug> {
+    tree: RBTree<T, usize>,
+    len: usize,
+}
+
+impl<T: Ord + Clone + Debug> RBMultiSet<T> {
+    pub fn new() -> RBMultiSet<T> {
+        RBMultiSet {
+            tree: RBTree::new(),
+            len: 0,
+        }
+    }
+
+    // 重複も含めた要素の数
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // 重複を除いた要素の数
+    pub fn distinct_len(&self) -> usize {
+        self.tree.len()
+    }
+
+    // 追加した後の個数を返す
+    pub fn insert(&mut self, value: T) -> usize {
+        self.len += 1;
+        match self.tree.search(&value) {
+            Some(node) => {
+                let count = self.tree.node_mut(node).value_mut();
+                *count += 1;
+                *count
+            },
+            None => {
+                self.tree.insert_multi(value, 1);
+                1
+            },
+        }
+    }
+
+    pub fn count<Q>(&self, value: &Q) -> usize
+        where T: Borrow<Q>, Q: Ord + ?Sized {
+        match self.tree.search(value) {
+            Some(node) => *self.tree.node(node).value(),
+            None => 0,
+        }
+    }
+
+    pub fn contains<Q>(&self, value: &Q) -> bool
+        where T: Borrow<Q>, Q: Ord + ?Sized {
+        self.tree.search(value).is_some()
+    }
+
+    // 1つだけ取り除く。存在しなかった場合はfalseを返す
+    pub fn remove_one<Q>(&mut self, value: &Q) -> bool
+        where T: Borrow<Q>, Q: Ord + ?Sized {
+        let node = match self.tree.search(value) {
+            Some(node) => node,
+            None => return false,
+        };
+        self.len -= 1;
+        let count = self.tree.node_mut(node).value_mut();
+        if *count > 1 {
+            *count -= 1;
+            return true;
+        }
+        self.tree.remove_node(node);
+        true
+    }
+
+    // 全て取り除いて、取り除いた個数を返す
+    pub fn remove_all<Q>(&mut self, value: &Q) -> usize
+        where T: Borrow<Q>, Q: Ord + ?Sized {
+        let count = match self.tree.search(value) {
+            Some(node) => self.tree.remove_node(node).1,
+            None => 0,
+        };
+        self.len -= count;
+        count
+    }
+
+    // 要素を個数の分だけ繰り返して昇順に返す
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        self.tree.iter().flat_map(|(value, count)| core::iter::repeat(value).take(*count))
+    }
+
+    // 要素とその個数を昇順に返す
+    pub fn counts(&self) -> impl Iterator<Item = (&T, usize)> + '_ {
+        self.tree.iter().map(|(value, count)| (value, *count))
+    }
+}
+
+impl<T: Ord + Clone + Debug> Default for RBMultiSet<T> {
+    fn default() -> RBMultiSet<T> {
+        RBMultiSet::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multimap_insertion_order() {
+        let mut map: RBMultiMap<usize, &str> = RBMultiMap::new();
+        map.insert(10, "a");
+        map.insert(5, "b");
+        map.insert(10, "c");
+        map.insert(20, "d");
+        map.insert(10, "e");
+        map.insert(5, "f");
+        assert_eq!(6, map.len());
+        assert_eq!(vec![&"a", &"c", &"e"], map.get_all(&10).map(|(_, value)| value).collect::<Vec<_>>());
+        assert_eq!(3, map.count(&10));
+        assert_eq!(2, map.count(&5));
+        assert_eq!(0, map.count(&7));
+        assert_eq!(
+            vec![(5, "b"), (5, "f"), (10, "a"), (10, "c"), (10, "e"), (20, "d")],
+            map.iter().map(|(key, value)| (*key, *value)).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn multimap_handle() {
+        // 同じ時刻に予定したタスクのうち、特定のものだけを取り消す
+        let mut map: RBMultiMap<usize, usize> = RBMultiMap::new();
+        let handles: Vec<NodeHandle> = (0..30).map(|task| map.insert_with_handle(task % 3, task)).collect();
+        assert_eq!(Some((1, 4)), map.remove_by_handle(handles[4]));
+        assert_eq!(None, map.remove_by_handle(handles[4]));
+        assert_eq!(Some((&1, &7)), map.get_by_handle(handles[7]));
+        assert_eq!(Some(1), map.remove_one(&1));
+        assert_eq!(Some(7), map.remove_one(&1));
+        assert_eq!(None, map.get_by_handle(handles[7]));
+        assert_eq!(27, map.len());
+    }
+
+    #[test]
+    fn multimap_remove() {
+        let mut map: RBMultiMap<usize, usize> = RBMultiMap::new();
+        for value in 0..30 {
+            map.insert(value % 3, value);
+        }
+        assert_eq!(Some(1), map.remove_one(&1));
+        assert_eq!(Some(4), map.remove_one(&1));
+        assert_eq!(None, map.remove_one(&5));
+        assert_eq!(28, map.len());
+        assert_eq!(8, map.count(&1));
+
+        assert_eq!((0..30).step_by(3).collect::<Vec<_>>(), map.remove_all(&0));
+        assert!(!map.contains_key(&0));
+        assert_eq!(18, map.len());
+        assert_eq!(Vec::<usize>::new(), map.remove_all(&0));
+        assert_eq!((7..30).step_by(3).collect::<Vec<_>>(), map.get_all(&1).map(|(_, value)| *value).collect::<Vec<_>>());
+        assert_eq!((2..30).step_by(3).collect::<Vec<_>>(), map.get_all(&2).map(|(_, value)| *value).collect::<Vec<_>>());
+
+        // 回転で同じkeyのノードが左右の部分木に分かれても数えられる
+        let mut map: RBMultiMap<usize, usize> = RBMultiMap::new();
+        for value in 0..1000 {
+            map.insert(value * 7 % 20, value);
+            if value % 5 == 0 { map.remove_one(&(value % 20)); }
+        }
+        for key in 0..21 {
+            assert_eq!(map.iter().filter(|(k, _)| **k == key).count(), map.count(&key));
+        }
+    }
+
+    #[test]
+    fn multiset() {
+        let mut set: RBMultiSet<&str> = RBMultiSet::new();
+        assert_eq!(1, set.insert("b"));
+        assert_eq!(1, set.insert("a"));
+        assert_eq!(2, set.insert("b"));
+        assert_eq!(3, set.insert("b"));
+        assert_eq!(4, set.len());
+        assert_eq!(2, set.distinct_len());
+        assert_eq!(3, set.count(&"b"));
+        assert_eq!(vec![&"a", &"b", &"b", &"b"], set.iter().collect::<Vec<_>>());
+        assert_eq!(vec![(&"a", 1), (&"b", 3)], set.counts().collect::<Vec<_>>());
+
+        assert!(set.remove_one(&"b"));
+        assert_eq!(2, set.count(&"b"));
+        assert!(set.remove_one(&"a"));
+        assert!(!set.contains(&"a"));
+        assert!(!set.remove_one(&"a"));
+        assert_eq!(2, set.remove_all(&"b"));
+        assert_eq!(0, set.remove_all(&"b"));
+        assert!(set.is_empty());
+    }
+}
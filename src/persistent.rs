@@ -0,0 +1,487 @@
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use alloc::vec;
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::fmt::Debug;
+
+use super::Color;
+
+type Link<K, V> = Option<Arc<PersistentNode<K, V>>>;
+
+// 親へのリンクを持たず、複数の版の木から共有されるノード
+// 共有しているノードは変更せず、変更する場合は根からのパスをコピーする
+#[derive(Clone)]
+struct PersistentNode<K, V> {
+    key: K,
+    value: V,
+    color: Color,
+    left: Link<K, V>,
+    right: Link<K, V>,
+}
+
+// insertとremoveで新しい版を返し、古い版もそのまま読めるRBTree
+// 新しい版は根から変更したノードまでのパスだけをコピーし、それ以外の部分木は古い版と共有する
+pub struct PersistentRBTree<K: Ord + Clone + Debug, V: Clone> {
+    root: Link<K, V>,
+    len: usize,
+}
+
+// 根を共有するだけなのでO(1)
+impl<K: Ord + Clone + Debug, V: Clone> Clone for PersistentRBTree<K, V> {
+    fn clone(&self) -> PersistentRBTree<K, V> {
+        PersistentRBTree {
+            root: self.root.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<K: Ord + Clone + Debug, V: Clone> PersistentRBTree<K, V> {
+    pub fn new() -> PersistentRBTree<K, V> {
+        PersistentRBTree {
+            root: None,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+        where K: Borrow<Q>, Q: Ord + ?Sized {
+        let mut node = self.root.as_ref();
+        while let Some(current_node) = node {
+            match key.cmp(current_node.key.borrow()) {
+                Ordering::Equal => return Some(&current_node.value),
+                Ordering::Less => node = current_node.left.as_ref(),
+                Ordering::Greater => node = current_node.right.as_ref(),
+            }
+        }
+        None
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+        where K: Borrow<Q>, Q: Ord + ?Sized {
+        self.get(key).is_some()
+    }
+
+    // keyにvalueを対応させた新しい版を返す。すでにkeyが存在する場合はvalueを上書きする
+    pub fn insert(&self, key: K, value: V) -> PersistentRBTree<K, V> {
+        let len = if self.contains_key(&key) { self.len } else { self.len + 1 };
+        PersistentRBTree {
+            root: blacken(&insert_node(&self.root, key, value)),
+            len,
+        }
+    }
+
+    // keyを取り除いた新しい版を返す。keyが存在しない場合は同じ木を共有する
+    pub fn remove<Q>(&self, key: &Q) -> PersistentRBTree<K, V>
+        where K: Borrow<Q>, Q: Ord + ?Sized {
+        if !self.contains_key(key) { return self.clone(); }
+        PersistentRBTree {
+            root: blacken(&remove_node(&self.root, key)),
+            len: self.len - 1,
+        }
+    }
+
+    // keyの昇順に並んだ(重複のない)要素から、O(n)で木を組み立てる。組み立て方はRBTree::from_sorted_iterと同じで、
+    // 中央の要素を根にして再帰的に組み立て、全てのノードが埋まっている段より深いノードだけを赤にする
+    pub(crate) fn from_sorted_iter<I: IntoIterator<Item = (K, V)>>(entries: I) -> PersistentRBTree<K, V> {
+        let entries: Vec<(K, V)> = entries.into_iter().collect();
+        let len = entries.len();
+        let mut full_depth = 0;
+        while (1 << (full_depth + 1)) - 1 <= len { full_depth += 1; }
+        PersistentRBTree {
+            root: build_sorted(&mut entries.into_iter(), len, 0, full_depth),
+            len,
+        }
+    }
+
+    // keyのvalueを書き換えられるようにする。根からkeyまでのパスのうち、他の版と共有しているノードだけをコピーする
+    // 共有していないノードはそのまま書き換えるので、他の版からは見えない
+    pub(crate) fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+        where K: Borrow<Q>, Q: Ord + ?Sized {
+        // 存在しないkeyの場合にパスをコピーしないように、先に確かめる
+        if !self.contains_key(key) { return None; }
+        get_mut_node(&mut self.root, key)
+    }
+
+    // 根のノードを他の版と共有しているかを返す
+    pub(crate) fn is_shared(&self) -> bool {
+        self.root.as_ref().is_some_and(|root| Arc::strong_count(root) > 1)
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        let mut iter = Iter { stack: vec![] };
+        iter.push_left(self.root.as_ref());
+        iter
+    }
+}
+
+impl<K: Ord + Clone + Debug, V: Clone> Default for PersistentRBTree<K, V> {
+    fn default() -> PersistentRBTree<K, V> {
+        PersistentRBTree::new()
+    }
+}
+
+fn node<K, V>(color: Color, left: Link<K, V>, key: K, value: V, right: Link<K, V>) -> Link<K, V> {
+    Some(Arc::new(PersistentNode { key, value, color, left, right }))
+}
+
+// 赤ノードの場合だけノードを返す
+fn red<K, V>(link: &Link<K, V>) -> Option<&Arc<PersistentNode<K, V>>> {
+    link.as_ref().filter(|node| node.color == Color::Red)
+}
+
+// 黒ノードの場合だけノードを返す(空の部分木は含まない)
+fn black<K, V>(link: &Link<K, V>) -> Option<&Arc<PersistentNode<K, V>>> {
+    link.as_ref().filter(|node| node.color == Color::Black)
+}
+
+fn is_red<K, V>(link: &Link<K, V>) -> bool {
+    red(link).is_some()
+}
+
+fn is_black<K, V>(link: &Link<K, V>) -> bool {
+    black(link).is_some()
+}
+
+fn build_sorted<K, V, I: Iterator<Item = (K, V)>>(entries: &mut I, len: usize, depth: usize, full_depth: usize) -> Link<K, V> {
+    if len == 0 { return None; }
+    let left_len = len / 2;
+    let left = build_sorted(entries, left_len, depth + 1, full_depth);
+    let (key, value) = entries.next().unwrap();
+    let right = build_sorted(entries, len - left_len - 1, depth + 1, full_depth);
+    let color = if depth < full_depth { Color::Black } else { Color::Red };
+    node(color, left, key, value, right)
+}
+
+// 色だけを変えたノードを作る
+fn recolor<K: Clone, V: Clone>(link: &Link<K, V>, color: Color) -> Link<K, V> {
+    let n = link.as_ref()?;
+    if n.color == color { return link.clone(); }
+    node(color, n.left.clone(), n.key.clone(), n.value.clone(), n.right.clone())
+}
+
+fn blacken<K: Clone, V: Clone>(link: &Link<K, V>) -> Link<K, V> {
+    recolor(link, Color::Black)
+}
+
+// 黒ノードを赤にして黒高さを1減らす
+fn redden<K: Clone, V: Clone>(link: &Link<K, V>) -> Link<K, V> {
+    assert!(is_black(link), "persistent rb-tree invariant violation");
+    recolor(link, Color::Red)
+}
+
+// 黒ノード(key, value)の下で赤が連続している場合に、赤の親と2つの黒の子に組み替える
+// 赤が連続していない場合は黒ノードを作る
+fn balance<K: Clone, V: Clone>(left: Link<K, V>, key: K, value: V, right: Link<K, V>) -> Link<K, V> {
+    if is_red(&left) && is_red(&right) {
+        return node(Color::Red, blacken(&left), key, value, blacken(&right));
+    }
+    if let Some(l) = red(&left) {
+        if is_red(&l.left) {
+            return node(
+                Color::Red,
+                blacken(&l.left),
+                l.key.clone(), l.value.clone(),
+                node(Color::Black, l.right.clone(), key, value, right),
+            );
+        }
+        if let Some(lr) = red(&l.right) {
+            return node(
+                Color::Red,
+                node(Color::Black, l.left.clone(), l.key.clone(), l.value.clone(), lr.left.clone()),
+                lr.key.clone(), lr.value.clone(),
+                node(Color::Black, lr.right.clone(), key, value, right),
+            );
+        }
+    }
+    if let Some(r) = red(&right) {
+        if is_red(&r.right) {
+            return node(
+                Color::Red,
+                node(Color::Black, left, key, value, r.left.clone()),
+                r.key.clone(), r.value.clone(),
+                blacken(&r.right),
+            );
+        }
+        if let Some(rl) = red(&r.left) {
+            return node(
+                Color::Red,
+                node(Color::Black, left, key, value, rl.left.clone()),
+                rl.key.clone(), rl.value.clone(),
+                node(Color::Black, rl.right.clone(), r.key.clone(), r.value.clone(), r.right.clone()),
+            );
+        }
+    }
+    node(Color::Black, left, key, value, right)
+}
+
+// 挿入した位置までのパスをコピーする。黒ノードの下で赤が連続した場合はbalanceで直す
+fn insert_node<K: Ord + Clone, V: Clone>(link: &Link<K, V>, key: K, value: V) -> Link<K, V> {
+    let n = match link {
+        Some(n) => n,
+        None => return node(Color::Red, None, key, value, None),
+    };
+    match key.cmp(&n.key) {
+        Ordering::Equal => node(n.color, n.left.clone(), key, value, n.right.clone()),
+        Ordering::Less => {
+            let left = insert_node(&n.left, key, value);
+            if n.color == Color::Black {
+                balance(left, n.key.clone(), n.value.clone(), n.right.clone())
+            } else {
+                node(Color::Red, left, n.key.clone(), n.value.clone(), n.right.clone())
+            }
+        },
+        Ordering::Greater => {
+            let right = insert_node(&n.right, key, value);
+            if n.color == Color::Black {
+                balance(n.left.clone(), n.key.clone(), n.value.clone(), right)
+            } else {
+                node(Color::Red, n.left.clone(), n.key.clone(), n.value.clone(), right)
+            }
+        },
+    }
+}
+
+// 削除した位置までのパスをコピーする
+// 黒ノードの部分木から削除すると黒高さが1減るので、balance_left/balance_rightで直す
+fn remove_node<K, V, Q>(link: &Link<K, V>, key: &Q) -> Link<K, V>
+    where K: Ord + Clone + Borrow<Q>, V: Clone, Q: Ord + ?Sized {
+    let n = link.as_ref()?;
+    match key.cmp(n.key.borrow()) {
+        Ordering::Equal => fuse(&n.left, &n.right),
+        Ordering::Less => {
+            let left = remove_node(&n.left, key);
+            if is_black(&n.left) {
+                balance_left(left, n.key.clone(), n.value.clone(), n.right.clone())
+            } else {
+                node(Color::Red, left, n.key.clone(), n.value.clone(), n.right.clone())
+            }
+        },
+        Ordering::Greater => {
+            let right = remove_node(&n.right, key);
+            if is_black(&n.right) {
+                balance_right(n.left.clone(), n.key.clone(), n.value.clone(), right)
+            } else {
+                node(Color::Red, n.left.clone(), n.key.clone(), n.value.clone(), right)
+            }
+        },
+    }
+}
+
+// keyまでのパスを降りながら、共有しているノードだけをArc::make_mutでコピーする
+fn get_mut_node<'a, K, V, Q>(link: &'a mut Link<K, V>, key: &Q) -> Option<&'a mut V>
+    where K: Clone + Borrow<Q>, V: Clone, Q: Ord + ?Sized {
+    let n = Arc::make_mut(link.as_mut()?);
+    match key.cmp(n.key.borrow()) {
+        Ordering::Equal => Some(&mut n.value),
+        Ordering::Less => get_mut_node(&mut n.left, key),
+        Ordering::Greater => get_mut_node(&mut n.right, key),
+    }
+}
+
+// 左の部分木の黒高さが右より1小さくなった場合に直す
+fn balance_left<K: Clone, V: Clone>(left: Link<K, V>, key: K, value: V, right: Link<K, V>) -> Link<K, V> {
+    if is_red(&left) {
+        return node(Color::Red, blacken(&left), key, value, right);
+    }
+    if is_black(&right) {
+        return balance(left, key, value, redden(&right));
+    }
+    // 右が赤の場合、右の左の子(黒)を根に持ってくる
+    let r = right.as_ref().expect("persistent rb-tree invariant violation");
+    let rl = black(&r.left).expect("persistent rb-tree invariant violation");
+    node(
+        Color::Red,
+        node(Color::Black, left, key, value, rl.left.clone()),
+        rl.key.clone(), rl.value.clone(),
+        balance(rl.right.clone(), r.key.clone(), r.value.clone(), redden(&r.right)),
+    )
+}
+
+// 右の部分木の黒高さが左より1小さくなった場合に直す
+fn balance_right<K: Clone, V: Clone>(left: Link<K, V>, key: K, value: V, right: Link<K, V>) -> Link<K, V> {
+    if is_red(&right) {
+        return node(Color::Red, left, key, value, blacken(&right));
+    }
+    if is_black(&left) {
+        return balance(redden(&left), key, value, right);
+    }
+    // 左が赤の場合、左の右の子(黒)を根に持ってくる
+    let l = left.as_ref().expect("persistent rb-tree invariant violation");
+    let lr = black(&l.right).expect("persistent rb-tree invariant violation");
+    node(
+        Color::Red,
+        balance(redden(&l.left), l.key.clone(), l.value.clone(), lr.left.clone()),
+        lr.key.clone(), lr.value.clone(),
+        node(Color::Black, lr.right.clone(), key, value, right),
+    )
+}
+
+// 削除したノードの左右の部分木(黒高さが同じ)を結合する
+fn fuse<K: Clone, V: Clone>(left: &Link<K, V>, right: &Link<K, V>) -> Link<K, V> {
+    let (l, r) = match (left, right) {
+        (None, _) => return right.clone(),
+        (_, None) => return left.clone(),
+        (Some(l), Some(r)) => (l, r),
+    };
+    match (l.color, r.color) {
+        (Color::Red, Color::Red) => {
+            let middle = fuse(&l.right, &r.left);
+            match red(&middle) {
+                Some(m) => node(
+                    Color::Red,
+                    node(Color::Red, l.left.clone(), l.key.clone(), l.value.clone(), m.left.clone()),
+                    m.key.clone(), m.value.clone(),
+                    node(Color::Red, m.right.clone(), r.key.clone(), r.value.clone(), r.right.clone()),
+                ),
+                None => node(
+                    Color::Red,
+                    l.left.clone(), l.key.clone(), l.value.clone(),
+                    node(Color::Red, middle, r.key.clone(), r.value.clone(), r.right.clone()),
+                ),
+            }
+        },
+        (Color::Black, Color::Black) => {
+            let middle = fuse(&l.right, &r.left);
+            match red(&middle) {
+                Some(m) => node(
+                    Color::Red,
+                    node(Color::Black, l.left.clone(), l.key.clone(), l.value.clone(), m.left.clone()),
+                    m.key.clone(), m.value.clone(),
+                    node(Color::Black, m.right.clone(), r.key.clone(), r.value.clone(), r.right.clone()),
+                ),
+                None => balance_left(
+                    l.left.clone(), l.key.clone(), l.value.clone(),
+                    node(Color::Black, middle, r.key.clone(), r.value.clone(), r.right.clone()),
+                ),
+            }
+        },
+        (_, Color::Red) => node(Color::Red, fuse(left, &r.left), r.key.clone(), r.value.clone(), r.right.clone()),
+        (Color::Red, _) => node(Color::Red, l.left.clone(), l.key.clone(), l.value.clone(), fuse(&l.right, right)),
+    }
+}
+
+pub struct Iter<'a, K, V> {
+    // 中間順でまだ返していないノード
+    stack: Vec<&'a PersistentNode<K, V>>,
+}
+
+impl<'a, K, V> Iter<'a, K, V> {
+    fn push_left(&mut self, link: Option<&'a Arc<PersistentNode<K, V>>>) {
+        let mut link = link;
+        while let Some(node) = link {
+            self.stack.push(node);
+            link = node.left.as_ref();
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        let node = self.stack.pop()?;
+        self.push_left(node.right.as_ref());
+        Some((&node.key, &node.value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    // 赤黒木の性質と木の順序を検証する
+    fn assert_persistent_rb_tree<K: Ord + Clone + Debug, V: Clone>(tree: &PersistentRBTree<K, V>) {
+        fn black_height<K: Ord, V>(link: &Link<K, V>, len: &mut usize) -> usize {
+            let node = match link {
+                Some(node) => node,
+                None => return 0,
+            };
+            if node.color == Color::Red {
+                assert!(!is_red(&node.left) && !is_red(&node.right));
+            }
+            if let Some(left) = &node.left { assert!(left.key < node.key); }
+            if let Some(right) = &node.right { assert!(node.key < right.key); }
+            *len += 1;
+            let left_height = black_height(&node.left, len);
+            let right_height = black_height(&node.right, len);
+            assert_eq!(left_height, right_height);
+            left_height + if node.color == Color::Black { 1 } else { 0 }
+        }
+        assert!(!is_red(&tree.root));
+        let mut len = 0;
+        black_height(&tree.root, &mut len);
+        assert_eq!(tree.len(), len);
+    }
+
+    #[test]
+    fn versions() {
+        let empty: PersistentRBTree<usize, &str> = PersistentRBTree::new();
+        let first = empty.insert(1, "a");
+        let second = first.insert(2, "b");
+        let third = second.insert(1, "c");
+        let fourth = third.remove(&2);
+        assert!(empty.is_empty());
+        assert_eq!(vec![(&1, &"a")], first.iter().collect::<Vec<_>>());
+        assert_eq!(vec![(&1, &"a"), (&2, &"b")], second.iter().collect::<Vec<_>>());
+        assert_eq!(vec![(&1, &"c"), (&2, &"b")], third.iter().collect::<Vec<_>>());
+        assert_eq!(vec![(&1, &"c")], fourth.iter().collect::<Vec<_>>());
+        assert_eq!(Some(&"b"), third.get(&2));
+        assert_eq!(None, fourth.get(&2));
+        assert_eq!(2, third.len());
+        assert_eq!(1, fourth.remove(&5).len());
+    }
+
+    #[test]
+    fn from_sorted_iter() {
+        for len in 0..70 {
+            let tree = PersistentRBTree::from_sorted_iter((0..len).map(|key| (key, key * 10)));
+            assert_persistent_rb_tree(&tree);
+            assert!(tree.iter().map(|(key, value)| (*key, *value)).eq((0..len).map(|key| (key, key * 10))));
+            // 組み立てた木もそのまま変更できる
+            let tree = tree.insert(len / 2, 0).remove(&(len / 3));
+            assert_persistent_rb_tree(&tree);
+        }
+    }
+
+    #[test]
+    fn random_operations() {
+        let mut versions: Vec<(PersistentRBTree<usize, usize>, BTreeMap<usize, usize>)> = vec![(PersistentRBTree::new(), BTreeMap::new())];
+        let mut seed = 7usize;
+        let mut random = |max: usize| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 33) % max
+        };
+        for step in 0..2000 {
+            // 過去の版からも新しい版を作る
+            let (tree, map) = versions[versions.len() - 1 - random(versions.len().min(5))].clone();
+            let key = random(300);
+            let (tree, map) = if random(3) == 0 {
+                let mut map = map;
+                map.remove(&key);
+                (tree.remove(&key), map)
+            } else {
+                let mut map = map;
+                map.insert(key, step);
+                (tree.insert(key, step), map)
+            };
+            assert_persistent_rb_tree(&tree);
+            versions.push((tree, map));
+        }
+        // 古い版も変わらずに読める
+        for (tree, map) in versions.iter() {
+            assert_eq!(map.len(), tree.len());
+            assert!(map.iter().eq(tree.iter()));
+        }
+    }
+}
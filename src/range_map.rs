@@ -0,0 +1,253 @@
+use core::fmt::Debug;
+use core::ops::{Bound, Range};
+
+use super::{Iter, RBTree};
+
+// 重ならない半開区間[start, end)にvalueを対応させるマップ
+// 開始位置をkeyにして、(終了位置, value)をvalueとして持つ
+// 同じvalueを持つ隣接した区間は常に結合しておく
+pub struct RangeMap<K: Ord + Clone + Debug, V> {
+    tree: RBTree<K, (K, V)>,
+}
+
+impl<K: Ord + Clone + Debug, V> RangeMap<K, V> {
+    pub fn new() -> RangeMap<K, V> {
+        RangeMap {
+            tree: RBTree::new(),
+        }
+    }
+
+    // 区間の数
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.len() == 0
+    }
+
+    // pointを含む区間とそのvalueを返す
+    // pointより前で最後に始まる区間だけを見ればよい
+    pub fn get_key_value(&self, point: &K) -> Option<(Range<K>, &V)> {
+        let node = self.tree.node(self.tree.upper_bound(Bound::Included(point))?);
+        let (end, value) = node.value();
+        if point < end {
+            Some((node.key.clone()..end.clone(), value))
+        } else {
+            None
+        }
+    }
+
+    pub fn get(&self, point: &K) -> Option<&V> {
+        self.get_key_value(point).map(|(_, value)| value)
+    }
+
+    pub fn contains_key(&self, point: &K) -> bool {
+        self.get_key_value(point).is_some()
+    }
+
+    // 区間を開始位置の昇順に返す
+    pub fn iter(&self) -> impl Iterator<Item = (Range<K>, &V)> + '_ {
+        self.tree.iter().map(|(start, (end, value))| (start.clone()..end.clone(), value))
+    }
+
+    // outerの中で、どの区間にも含まれない部分を昇順に返す
+    pub fn gaps(&self, outer: Range<K>) -> Gaps<'_, K, V> {
+        if outer.start >= outer.end {
+            return Gaps { iter: self.tree.range(outer.clone()), cursor: None, end: outer.end };
+        }
+        // outerの開始位置にかかっている区間から辿る
+        let iter = match self.tree.upper_bound(Bound::Included(&outer.start)) {
+            Some(node) => self.tree.range((Bound::Included(&self.tree.node(node).key), Bound::Unbounded)),
+            None => self.tree.iter(),
+        };
+        Gaps { iter, cursor: Some(outer.start), end: outer.end }
+    }
+}
+
+impl<K: Ord + Clone + Debug, V> Default for RangeMap<K, V> {
+    fn default() -> RangeMap<K, V> {
+        RangeMap::new()
+    }
+}
+
+impl<K: Ord + Clone + Debug, V: Clone + PartialEq> RangeMap<K, V> {
+    // rangeにvalueを対応させる。既存の区間と重なる部分は上書きする
+    pub fn insert(&mut self, range: Range<K>, value: V) {
+        assert!(range.start < range.end, "range start must be less than end. start: {:?}, end: {:?}", range.start, range.end);
+        self.remove(range.clone());
+        let start = range.start;
+        let mut end = range.end;
+        // 後ろに隣接する区間が同じvalueを持つ場合は結合する
+        if let Some(node) = self.tree.search(&end) {
+            if self.tree.node(node).value().1 == value {
+                end = self.tree.remove_node(node).1 .0;
+            }
+        }
+        // 前に隣接する区間が同じvalueを持つ場合は、その区間を伸ばす
+        if let Some(node) = self.tree.upper_bound(Bound::Excluded(&start)) {
+            let (previous_end, previous_value) = self.tree.node_mut(node).value_mut();
+            if *previous_end == start && *previous_value == value {
+                *previous_end = end;
+                return;
+            }
+        }
+        // 重なる区間は全て取り除いているので、同じkeyは存在しない
+        self.tree.insert_multi(start, (end, value));
+    }
+
+    // rangeに含まれる部分を取り除く。はみ出している区間は切り詰めたり分割したりする
+    pub fn remove(&mut self, range: Range<K>) {
+        if range.start >= range.end { return; }
+        // 開始位置より前から始まって開始位置にかかっている区間は、開始位置までに切り詰める
+        // rangeを内側に含む場合は、後ろ側を別の区間として残す
+        let mut tail = None;
+        if let Some(node) = self.tree.upper_bound(Bound::Excluded(&range.start)) {
+            let (end, value) = self.tree.node_mut(node).value_mut();
+            if *end > range.end {
+                tail = Some((end.clone(), value.clone()));
+            }
+            if *end > range.start {
+                *end = range.start.clone();
+            }
+        }
+        // 開始位置が範囲内にある区間を取り除き、最後の区間がはみ出していれば後ろ側を残す
+        let removed = self.tree.remove_range(range.clone());
+        if let Some((_, (end, value))) = removed.into_iter().next_back() {
+            if end > range.end {
+                tail = Some((end, value));
+            }
+        }
+        if let Some(tail) = tail {
+            self.tree.insert_multi(range.end, tail);
+        }
+    }
+}
+
+// どの区間にも含まれない部分を返すイテレータ
+pub struct Gaps<'a, K: Ord + Clone + Debug, V> {
+    iter: Iter<'a, K, (K, V)>,
+    // まだ返していない部分の開始位置。Noneの場合は終わり
+    cursor: Option<K>,
+    end: K,
+}
+
+impl<'a, K: Ord + Clone + Debug, V> Iterator for Gaps<'a, K, V> {
+    type Item = Range<K>;
+
+    fn next(&mut self) -> Option<Range<K>> {
+        let mut cursor = self.cursor.take()?;
+        for (start, (end, _)) in self.iter.by_ref() {
+            if *start >= self.end { break; }
+            // outerの開始位置より前で終わる区間
+            if *end <= cursor { continue; }
+            if *start > cursor {
+                self.cursor = Some(end.clone());
+                return Some(cursor..start.clone());
+            }
+            cursor = end.clone();
+            if cursor >= self.end { return None; }
+        }
+        if cursor < self.end {
+            Some(cursor..self.end.clone())
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranges(map: &RangeMap<usize, char>) -> Vec<(Range<usize>, char)> {
+        map.iter().map(|(range, value)| (range, *value)).collect()
+    }
+
+    #[test]
+    fn insert_split_merge() {
+        let mut map: RangeMap<usize, char> = RangeMap::new();
+        map.insert(10..20, 'a');
+        map.insert(30..40, 'b');
+        assert_eq!(vec![(10..20, 'a'), (30..40, 'b')], ranges(&map));
+
+        // 内側に挿入すると分割される
+        map.insert(12..15, 'c');
+        assert_eq!(vec![(10..12, 'a'), (12..15, 'c'), (15..20, 'a'), (30..40, 'b')], ranges(&map));
+
+        // 重なる部分は切り詰められる
+        map.insert(18..35, 'd');
+        assert_eq!(vec![(10..12, 'a'), (12..15, 'c'), (15..18, 'a'), (18..35, 'd'), (35..40, 'b')], ranges(&map));
+
+        // 同じvalueを持つ隣接した区間は結合される
+        map.insert(12..15, 'a');
+        assert_eq!(vec![(10..18, 'a'), (18..35, 'd'), (35..40, 'b')], ranges(&map));
+        map.insert(40..45, 'b');
+        map.insert(5..10, 'a');
+        assert_eq!(vec![(5..18, 'a'), (18..35, 'd'), (35..45, 'b')], ranges(&map));
+
+        assert_eq!(Some(&'a'), map.get(&5));
+        assert_eq!(Some(&'d'), map.get(&34));
+        assert_eq!(Some((35..45, &'b')), map.get_key_value(&35));
+        assert_eq!(None, map.get(&45));
+        assert_eq!(None, map.get(&0));
+
+        map.remove(15..40);
+        assert_eq!(vec![(5..15, 'a'), (40..45, 'b')], ranges(&map));
+        map.remove(0..100);
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn gaps() {
+        let mut map: RangeMap<usize, char> = RangeMap::new();
+        assert_eq!(vec![0..10], map.gaps(0..10).collect::<Vec<_>>());
+        map.insert(10..20, 'a');
+        map.insert(30..40, 'b');
+        map.insert(40..50, 'c');
+        assert_eq!(vec![0..10, 20..30, 50..60], map.gaps(0..60).collect::<Vec<_>>());
+        assert_eq!(vec![20..30], map.gaps(15..45).collect::<Vec<_>>());
+        assert_eq!(vec![25..30], map.gaps(25..35).collect::<Vec<_>>());
+        assert_eq!(Vec::<Range<usize>>::new(), map.gaps(12..18).collect::<Vec<_>>());
+        assert_eq!(Vec::<Range<usize>>::new(), map.gaps(30..30).collect::<Vec<_>>());
+        assert_eq!(vec![50..51], map.gaps(45..51).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn random_operations() {
+        let mut map: RangeMap<usize, char> = RangeMap::new();
+        // 位置ごとのvalue
+        let mut points: Vec<Option<char>> = vec![None; 100];
+        let mut seed = 42usize;
+        let mut random = |max: usize| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 33) % max
+        };
+        for step in 0..500 {
+            let start = random(100);
+            let end = start + 1 + random(100 - start);
+            if step % 3 == 0 {
+                map.remove(start..end);
+                points[start..end].fill(None);
+            } else {
+                let value = ['a', 'b', 'c'][random(3)];
+                map.insert(start..end, value);
+                points[start..end].fill(Some(value));
+            }
+            // 同じvalueが続く部分ごとに1つの区間になっている
+            let mut expected = vec![];
+            let mut position = 0;
+            while position < points.len() {
+                let run_end = (position..points.len()).find(|i| points[*i] != points[position]).unwrap_or(points.len());
+                if let Some(value) = points[position] { expected.push((position..run_end, value)); }
+                position = run_end;
+            }
+            assert_eq!(expected, ranges(&map));
+        }
+        for (point, value) in points.iter().enumerate() {
+            assert_eq!(value.as_ref(), map.get(&point));
+        }
+        let expected_gaps: Vec<usize> = (20..80).filter(|point| points[*point].is_none()).collect();
+        assert_eq!(expected_gaps, map.gaps(20..80).flatten().collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,444 @@
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::fmt::Debug;
+use core::iter::Peekable;
+use core::ops::{BitAnd, BitOr, BitXor, RangeBounds, Sub};
+
+use super::{Iter as TreeIter, RBTree};
+
+// 集合の大きさがこの倍率以上違う場合は、両方を辿らずに小さい方の要素を大きい方から探す
+const SEARCH_RATIO: usize = 16;
+
+// valueを持たないRBTreeをそのまま集合として使う
+pub struct RBSet<T: Ord + Clone + Debug> {
+    tree: RBTree<T, ()>,
+}
+
+impl<T: Ord + Clone + Debug> RBSet<T> {
+    pub fn new() -> RBSet<T> {
+        RBSet {
+            tree: RBTree::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.len() == 0
+    }
+
+    // すでに存在していた場合はfalseを返す
+    pub fn insert(&mut self, value: T) -> bool {
+        self.tree.insert(value, ()).is_none()
+    }
+
+    // 存在しなかった場合はfalseを返す
+    pub fn remove<Q>(&mut self, value: &Q) -> bool
+        where T: Borrow<Q>, Q: Ord + ?Sized {
+        match self.tree.search(value) {
+            Some(node) => {
+                self.tree.remove_node(node);
+                true
+            },
+            None => false,
+        }
+    }
+
+    pub fn contains<Q>(&self, value: &Q) -> bool
+        where T: Borrow<Q>, Q: Ord + ?Sized {
+        self.tree.search(value).is_some()
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { iter: self.tree.iter() }
+    }
+
+    pub fn range<Q, R>(&self, range: R) -> Iter<'_, T>
+        where T: Borrow<Q>, Q: Ord + ?Sized, R: RangeBounds<Q> {
+        Iter { iter: self.tree.range(range) }
+    }
+
+    pub fn first(&self) -> Option<&T> {
+        self.tree.first_key_value().map(|(value, _)| value)
+    }
+
+    pub fn last(&self) -> Option<&T> {
+        self.tree.last_key_value().map(|(value, _)| value)
+    }
+
+    pub fn pop_first(&mut self) -> Option<T> {
+        self.tree.pop_first().map(|(value, _)| value)
+    }
+
+    pub fn pop_last(&mut self) -> Option<T> {
+        self.tree.pop_last().map(|(value, _)| value)
+    }
+
+    pub fn union<'a>(&'a self, other: &'a RBSet<T>) -> Union<'a, T> {
+        Union {
+            a: self.iter().peekable(),
+            b: other.iter().peekable(),
+        }
+    }
+
+    pub fn intersection<'a>(&'a self, other: &'a RBSet<T>) -> Intersection<'a, T> {
+        let (smaller, larger) = if self.len() <= other.len() { (self, other) } else { (other, self) };
+        if smaller.len() * SEARCH_RATIO < larger.len() {
+            return Intersection::Search { small: smaller.iter(), large: larger };
+        }
+        Intersection::Stitch {
+            a: self.iter().peekable(),
+            b: other.iter().peekable(),
+        }
+    }
+
+    pub fn difference<'a>(&'a self, other: &'a RBSet<T>) -> Difference<'a, T> {
+        if self.len() * SEARCH_RATIO < other.len() {
+            return Difference::Search { a: self.iter(), b: other };
+        }
+        Difference::Stitch {
+            a: self.iter(),
+            b: other.iter().peekable(),
+        }
+    }
+
+    pub fn symmetric_difference<'a>(&'a self, other: &'a RBSet<T>) -> SymmetricDifference<'a, T> {
+        SymmetricDifference {
+            a: self.iter().peekable(),
+            b: other.iter().peekable(),
+        }
+    }
+
+    pub fn is_subset(&self, other: &RBSet<T>) -> bool {
+        if self.len() > other.len() { return false; }
+        self.iter().all(|value| other.contains(value))
+    }
+
+    pub fn is_superset(&self, other: &RBSet<T>) -> bool {
+        other.is_subset(self)
+    }
+
+    pub fn is_disjoint(&self, other: &RBSet<T>) -> bool {
+        // 小さい方の集合の要素を大きい方から探す
+        let (smaller, larger) = if self.len() <= other.len() { (self, other) } else { (other, self) };
+        smaller.iter().all(|value| !larger.contains(value))
+    }
+}
+
+impl<T: Ord + Clone + Debug> Default for RBSet<T> {
+    fn default() -> RBSet<T> {
+        RBSet::new()
+    }
+}
+
+pub struct Iter<'a, T: Ord + Clone + Debug> {
+    iter: TreeIter<'a, T, ()>,
+}
+
+impl<'a, T: Ord + Clone + Debug> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.iter.next().map(|(value, _)| value)
+    }
+}
+
+impl<'a, T: Ord + Clone + Debug> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<&'a T> {
+        self.iter.next_back().map(|(value, _)| value)
+    }
+}
+
+impl<'a, T: Ord + Clone + Debug> IntoIterator for &'a RBSet<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T: Ord + Clone + Debug> RBSet<T> {
+    // 昇順に並んだ(重複のない)要素からO(n)で集合を組み立てる
+    fn from_sorted_iter<I: IntoIterator<Item = T>>(values: I) -> RBSet<T> {
+        RBSet {
+            tree: RBTree::from_sorted_iter(values.into_iter().map(|value| (value, ()))),
+        }
+    }
+}
+
+pub struct Union<'a, T: Ord + Clone + Debug> {
+    a: Peekable<Iter<'a, T>>,
+    b: Peekable<Iter<'a, T>>,
+}
+
+impl<'a, T: Ord + Clone + Debug> Iterator for Union<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let ordering = match (self.a.peek(), self.b.peek()) {
+            (Some(a), Some(b)) => a.cmp(b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => return None,
+        };
+        match ordering {
+            Ordering::Less => self.a.next(),
+            Ordering::Greater => self.b.next(),
+            Ordering::Equal => {
+                self.b.next();
+                self.a.next()
+            },
+        }
+    }
+}
+
+pub enum Intersection<'a, T: Ord + Clone + Debug> {
+    // 両方の集合を先頭から同時に辿る
+    Stitch {
+        a: Peekable<Iter<'a, T>>,
+        b: Peekable<Iter<'a, T>>,
+    },
+    // 小さい方の集合の要素を大きい方の集合から探す
+    Search {
+        small: Iter<'a, T>,
+        large: &'a RBSet<T>,
+    },
+}
+
+impl<'a, T: Ord + Clone + Debug> Iterator for Intersection<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        match self {
+            Intersection::Stitch { a, b } => loop {
+                match a.peek()?.cmp(b.peek()?) {
+                    Ordering::Less => { a.next(); },
+                    Ordering::Greater => { b.next(); },
+                    Ordering::Equal => {
+                        b.next();
+                        return a.next();
+                    },
+                }
+            },
+            Intersection::Search { small, large } => small.find(|value| large.contains(*value)),
+        }
+    }
+}
+
+pub enum Difference<'a, T: Ord + Clone + Debug> {
+    // 両方の集合を先頭から同時に辿る
+    Stitch {
+        a: Iter<'a, T>,
+        b: Peekable<Iter<'a, T>>,
+    },
+    // 自分の要素を相手の集合から探す
+    Search {
+        a: Iter<'a, T>,
+        b: &'a RBSet<T>,
+    },
+}
+
+impl<'a, T: Ord + Clone + Debug> Iterator for Difference<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        match self {
+            Difference::Stitch { a, b } => loop {
+                let value = a.next()?;
+                // 相手の集合の、valueより小さい要素を読み飛ばす
+                while b.peek().is_some_and(|other| *other < value) {
+                    b.next();
+                }
+                if b.peek() != Some(&value) { return Some(value); }
+            },
+            Difference::Search { a, b } => a.find(|value| !b.contains(*value)),
+        }
+    }
+}
+
+pub struct SymmetricDifference<'a, T: Ord + Clone + Debug> {
+    a: Peekable<Iter<'a, T>>,
+    b: Peekable<Iter<'a, T>>,
+}
+
+impl<'a, T: Ord + Clone + Debug> Iterator for SymmetricDifference<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        loop {
+            let ordering = match (self.a.peek(), self.b.peek()) {
+                (Some(a), Some(b)) => a.cmp(b),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => return None,
+            };
+            match ordering {
+                Ordering::Less => return self.a.next(),
+                Ordering::Greater => return self.b.next(),
+                Ordering::Equal => {
+                    self.a.next();
+                    self.b.next();
+                },
+            }
+        }
+    }
+}
+
+impl<T: Ord + Clone + Debug> BitOr<&RBSet<T>> for &RBSet<T> {
+    type Output = RBSet<T>;
+
+    fn bitor(self, rhs: &RBSet<T>) -> RBSet<T> {
+        RBSet::from_sorted_iter(self.union(rhs).cloned())
+    }
+}
+
+impl<T: Ord + Clone + Debug> BitAnd<&RBSet<T>> for &RBSet<T> {
+    type Output = RBSet<T>;
+
+    fn bitand(self, rhs: &RBSet<T>) -> RBSet<T> {
+        RBSet::from_sorted_iter(self.intersection(rhs).cloned())
+    }
+}
+
+impl<T: Ord + Clone + Debug> Sub<&RBSet<T>> for &RBSet<T> {
+    type Output = RBSet<T>;
+
+    fn sub(self, rhs: &RBSet<T>) -> RBSet<T> {
+        RBSet::from_sorted_iter(self.difference(rhs).cloned())
+    }
+}
+
+impl<T: Ord + Clone + Debug> BitXor<&RBSet<T>> for &RBSet<T> {
+    type Output = RBSet<T>;
+
+    fn bitxor(self, rhs: &RBSet<T>) -> RBSet<T> {
+        RBSet::from_sorted_iter(self.symmetric_difference(rhs).cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ops::Bound;
+
+    #[test]
+    fn insert_remove_contains() {
+        let mut set: RBSet<usize> = RBSet::new();
+        assert!(set.is_empty());
+        assert!(set.insert(10));
+        assert!(set.insert(3));
+        assert!(set.insert(20));
+        assert!(!set.insert(10));
+        assert_eq!(3, set.len());
+        assert!(set.contains(&3));
+        assert!(!set.contains(&4));
+
+        assert!(set.remove(&3));
+        assert!(!set.remove(&3));
+        assert_eq!(2, set.len());
+        assert!(!set.contains(&3));
+    }
+
+    #[test]
+    fn iter_range() {
+        let mut set: RBSet<String> = RBSet::new();
+        for value in &["d", "a", "c", "e", "b"] {
+            set.insert(value.to_string());
+        }
+        assert_eq!(vec!["a", "b", "c", "d", "e"], set.iter().collect::<Vec<_>>());
+        assert_eq!(vec!["e", "d", "c", "b", "a"], set.iter().rev().collect::<Vec<_>>());
+        assert_eq!(vec!["b", "c"], set.range::<str, _>((Bound::Included("b"), Bound::Excluded("d"))).collect::<Vec<_>>());
+        assert_eq!(vec!["c", "d", "e"], set.range::<str, _>((Bound::Included("bb"), Bound::Unbounded)).collect::<Vec<_>>());
+        assert_eq!(0, set.range::<str, _>((Bound::Excluded("e"), Bound::Unbounded)).count());
+        assert!(set.contains("c"));
+    }
+
+    #[test]
+    fn first_last_pop() {
+        let mut set: RBSet<usize> = RBSet::new();
+        assert_eq!(None, set.first());
+        assert_eq!(None, set.pop_first());
+        for value in 1..=10 {
+            set.insert(value);
+        }
+        assert_eq!(Some(&1), set.first());
+        assert_eq!(Some(&10), set.last());
+        assert_eq!(Some(1), set.pop_first());
+        assert_eq!(Some(10), set.pop_last());
+        assert_eq!(Some(2), set.pop_first());
+        assert_eq!(7, set.len());
+        assert_eq!((3..=9).collect::<Vec<_>>(), set.iter().cloned().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn subset_superset_disjoint() {
+        let mut a: RBSet<usize> = RBSet::new();
+        let mut b: RBSet<usize> = RBSet::new();
+        let mut c: RBSet<usize> = RBSet::new();
+        for value in 1..=3 { a.insert(value); }
+        for value in 1..=5 { b.insert(value); }
+        for value in 6..=8 { c.insert(value); }
+        assert!(a.is_subset(&b));
+        assert!(!b.is_subset(&a));
+        assert!(b.is_superset(&a));
+        assert!(a.is_disjoint(&c));
+        assert!(!a.is_disjoint(&b));
+        assert!(RBSet::new().is_subset(&a));
+    }
+
+    fn set(values: &[usize]) -> RBSet<usize> {
+        let mut set = RBSet::new();
+        for value in values {
+            set.insert(*value);
+        }
+        set
+    }
+
+    #[test]
+    fn union_intersection_difference() {
+        let a = set(&[1, 3, 5, 7, 9]);
+        let b = set(&[3, 4, 5, 6]);
+        assert_eq!(vec![1, 3, 4, 5, 6, 7, 9], a.union(&b).cloned().collect::<Vec<_>>());
+        assert_eq!(vec![3, 5], a.intersection(&b).cloned().collect::<Vec<_>>());
+        assert_eq!(vec![1, 7, 9], a.difference(&b).cloned().collect::<Vec<_>>());
+        assert_eq!(vec![4, 6], b.difference(&a).cloned().collect::<Vec<_>>());
+        assert_eq!(vec![1, 4, 6, 7, 9], a.symmetric_difference(&b).cloned().collect::<Vec<_>>());
+
+        let empty = RBSet::new();
+        assert_eq!(0, a.intersection(&empty).count());
+        assert_eq!(a.len(), a.union(&empty).count());
+        assert_eq!(a.len(), a.difference(&empty).count());
+    }
+
+    #[test]
+    fn intersection_difference_search() {
+        // 大きさが大きく違う場合は小さい方の要素を大きい方から探す
+        let small = set(&[0, 50, 99, 500]);
+        let large = set(&(0..100).collect::<Vec<_>>());
+        assert!(matches!(small.intersection(&large), Intersection::Search { .. }));
+        assert!(matches!(large.intersection(&small), Intersection::Search { .. }));
+        assert!(matches!(small.difference(&large), Difference::Search { .. }));
+        assert_eq!(vec![0, 50, 99], small.intersection(&large).cloned().collect::<Vec<_>>());
+        assert_eq!(vec![0, 50, 99], large.intersection(&small).cloned().collect::<Vec<_>>());
+        assert_eq!(vec![500], small.difference(&large).cloned().collect::<Vec<_>>());
+        assert_eq!(97, large.difference(&small).count());
+    }
+
+    #[test]
+    fn operators() {
+        let a = set(&[1, 2, 3, 4]);
+        let b = set(&[3, 4, 5]);
+        assert_eq!(vec![1, 2, 3, 4, 5], (&a | &b).iter().cloned().collect::<Vec<_>>());
+        assert_eq!(vec![3, 4], (&a & &b).iter().cloned().collect::<Vec<_>>());
+        assert_eq!(vec![1, 2], (&a - &b).iter().cloned().collect::<Vec<_>>());
+        assert_eq!(vec![1, 2, 5], (&a ^ &b).iter().cloned().collect::<Vec<_>>());
+
+        let union = &a | &b;
+        assert_eq!(5, union.len());
+        assert!(union.contains(&5));
+        assert!(a.is_subset(&union));
+    }
+}
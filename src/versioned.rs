@@ -0,0 +1,153 @@
+use alloc::vec::Vec;
+use alloc::vec;
+use core::borrow::Borrow;
+use core::fmt::Debug;
+
+use super::persistent::{Iter, PersistentRBTree};
+
+// commitするたびに版番号を振り、過去の版の状態も読めるRBTree
+// 各版はPersistentRBTreeなので、変更のなかった部分木は版の間で共有される
+pub struct VersionedRBTree<K: Ord + Clone + Debug, V: Clone> {
+    // まだcommitしていない変更を含む状態
+    current: PersistentRBTree<K, V>,
+    // (版番号, その版の状態)を版番号の昇順に持つ
+    versions: Vec<(u64, PersistentRBTree<K, V>)>,
+    next_version: u64,
+}
+
+impl<K: Ord + Clone + Debug, V: Clone> VersionedRBTree<K, V> {
+    // 空の状態を版0とする
+    pub fn new() -> VersionedRBTree<K, V> {
+        VersionedRBTree {
+            current: PersistentRBTree::new(),
+            versions: vec![(0, PersistentRBTree::new())],
+            next_version: 1,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.current.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.current.is_empty()
+    }
+
+    pub fn insert(&mut self, key: K, value: V) {
+        self.current = self.current.insert(key, value);
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q)
+        where K: Borrow<Q>, Q: Ord + ?Sized {
+        self.current = self.current.remove(key);
+    }
+
+    // commitしていない変更も含めた現在の状態から探す
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+        where K: Borrow<Q>, Q: Ord + ?Sized {
+        self.current.get(key)
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        self.current.iter()
+    }
+
+    // 現在の状態を新しい版として記録して、その版番号を返す
+    pub fn commit(&mut self) -> u64 {
+        let version = self.next_version;
+        self.next_version += 1;
+        self.versions.push((version, self.current.clone()));
+        version
+    }
+
+    // 最後にcommitした版の番号
+    pub fn latest_version(&self) -> Option<u64> {
+        self.versions.last().map(|(version, _)| *version)
+    }
+
+    // 読むことのできる版の番号を昇順に返す
+    pub fn versions(&self) -> impl Iterator<Item = u64> + '_ {
+        self.versions.iter().map(|(version, _)| *version)
+    }
+
+    // 版の状態を返す。commitしていない版やcompactで取り除いた版の場合はNone
+    pub fn snapshot(&self, version: u64) -> Option<&PersistentRBTree<K, V>> {
+        let index = self.versions.binary_search_by_key(&version, |(version, _)| *version).ok()?;
+        Some(&self.versions[index].1)
+    }
+
+    pub fn get_at<Q>(&self, key: &Q, version: u64) -> Option<&V>
+        where K: Borrow<Q>, Q: Ord + ?Sized {
+        self.snapshot(version)?.get(key)
+    }
+
+    pub fn iter_at(&self, version: u64) -> Option<Iter<'_, K, V>> {
+        Some(self.snapshot(version)?.iter())
+    }
+
+    // before_versionより前の版を取り除く。他の版と共有していないノードはここで解放される
+    // 最新の版(next_version - 1)は常に残すので、before_versionが最新の版より大きくても全ての版がなくなることはない
+    pub fn compact(&mut self, before_version: u64) {
+        let before_version = before_version.min(self.next_version - 1);
+        self.versions.retain(|(version, _)| *version >= before_version);
+    }
+}
+
+impl<K: Ord + Clone + Debug, V: Clone> Default for VersionedRBTree<K, V> {
+    fn default() -> VersionedRBTree<K, V> {
+        VersionedRBTree::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions() {
+        let mut tree: VersionedRBTree<&str, usize> = VersionedRBTree::new();
+        tree.insert("a", 1);
+        tree.insert("b", 2);
+        assert_eq!(1, tree.commit());
+        tree.insert("a", 10);
+        tree.remove(&"b");
+        tree.insert("c", 3);
+        // commitしていない変更は現在の状態からだけ読める
+        assert_eq!(Some(&10), tree.get(&"a"));
+        assert_eq!(Some(&1), tree.get_at(&"a", 1));
+        assert_eq!(2, tree.commit());
+        tree.remove(&"a");
+        assert_eq!(3, tree.commit());
+
+        assert_eq!(None, tree.get_at(&"a", 0));
+        assert_eq!(Some(&1), tree.get_at(&"a", 1));
+        assert_eq!(Some(&10), tree.get_at(&"a", 2));
+        assert_eq!(None, tree.get_at(&"a", 3));
+        assert_eq!(None, tree.get_at(&"a", 4));
+        assert_eq!(vec![(&"a", &1), (&"b", &2)], tree.iter_at(1).unwrap().collect::<Vec<_>>());
+        assert_eq!(vec![(&"a", &10), (&"c", &3)], tree.iter_at(2).unwrap().collect::<Vec<_>>());
+        assert_eq!(vec![(&"c", &3)], tree.iter_at(3).unwrap().collect::<Vec<_>>());
+        assert_eq!(vec![(&"c", &3)], tree.iter().collect::<Vec<_>>());
+        assert_eq!(Some(3), tree.latest_version());
+
+        tree.compact(2);
+        assert_eq!(vec![2, 3], tree.versions().collect::<Vec<_>>());
+        assert!(tree.iter_at(1).is_none());
+        assert_eq!(None, tree.get_at(&"a", 1));
+        assert_eq!(Some(&10), tree.get_at(&"a", 2));
+
+        // compactした後も版番号は増え続ける
+        tree.insert("d", 4);
+        assert_eq!(4, tree.commit());
+        assert_eq!(2, tree.len());
+
+        // 最新の版より後を指定しても、最新の版は残る
+        tree.compact(u64::MAX);
+        assert_eq!(vec![4], tree.versions().collect::<Vec<_>>());
+        assert_eq!(Some(4), tree.latest_version());
+        assert_eq!(Some(&4), tree.get_at(&"d", 4));
+        assert_eq!(Some(&3), tree.get_at(&"c", 4));
+        tree.compact(u64::MAX);
+        assert_eq!(Some(4), tree.latest_version());
+    }
+}